@@ -2,101 +2,49 @@
 use std::fs::File;
 use std::io::Write;
 
-fn main() {
-    let mut output = String::from("_version: 2\n\n");
-
-    // Basic numbers 0-19
-    let ones = [
-        "zero",
-        "one",
-        "two",
-        "three",
-        "four",
-        "five",
-        "six",
-        "seven",
-        "eight",
-        "nine",
-        "ten",
-        "eleven",
-        "twelve",
-        "thirteen",
-        "fourteen",
-        "fifteen",
-        "sixteen",
-        "seventeen",
-        "eighteen",
-        "nineteen",
-    ];
-
-    for (i, word) in ones.iter().enumerate() {
-        output.push_str(&format!("numbers.{}:\n  en: {}\n", i, word));
-    }
-
-    output.push('\n');
+/// Fraction words for one locale. Whole numbers are spelled by `distance::number_speller`.
+struct Numbers {
+    locale: &'static str,
+    fractions: [&'static str; 4],
+}
 
-    // Tens
-    let tens = [
-        "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
-    ];
+const FRACTION_KEYS: [&str; 4] = [
+    "numbers.one_eighth",
+    "numbers.one_quarter",
+    "numbers.one_half",
+    "numbers.three_quarter",
+];
+
+const LOCALES: [Numbers; 4] = [
+    Numbers {
+        locale: "en",
+        fractions: ["one eighth", "one quarter", "one half", "three quarter"],
+    },
+    Numbers {
+        locale: "de",
+        fractions: ["ein Achtel", "ein Viertel", "ein halb", "drei Viertel"],
+    },
+    Numbers {
+        locale: "fr",
+        fractions: ["un huitième", "un quart", "un demi", "trois quarts"],
+    },
+    Numbers {
+        locale: "es",
+        fractions: ["un octavo", "un cuarto", "medio", "tres cuartos"],
+    },
+];
 
-    for (i, word) in tens.iter().enumerate() {
-        output.push_str(&format!("numbers.{}:\n  en: {}\n", (i + 2) * 10, word));
-    }
-
-    output.push('\n');
+fn main() {
+    let mut output = String::from("_version: 2\n\n");
 
-    // Combined numbers (21-99)
-    for (tens_i, tens_word) in tens.iter().enumerate() {
-        #[allow(clippy::needless_range_loop)]
-        for ones_i in 1..10 {
-            output.push_str(&format!(
-                "numbers.combined.{}_{}:\n  en: {} {}\n",
-                (tens_i + 2) * 10,
-                ones_i,
-                tens_word,
-                ones[ones_i]
-            ));
+    // Fractions
+    for (i, key) in FRACTION_KEYS.iter().enumerate() {
+        output.push_str(&format!("{}:\n", key));
+        for numbers in LOCALES.iter() {
+            output.push_str(&format!("  {}: {}\n", numbers.locale, numbers.fractions[i]));
         }
     }
 
-    output.push('\n');
-
-    // Large numbers and special cases
-    let special_cases = [
-        ("numbers.hundred", "hundred"),
-        ("numbers.thousand", "thousand"),
-        ("numbers.million", "million"),
-        ("numbers.billion", "billion"),
-        ("numbers.negative", "negative"),
-        ("numbers.one_eighth", "one eighth"),
-        ("numbers.one_quarter", "one quarter"),
-        ("numbers.one_half", "one half"),
-        ("numbers.three_quarter", "three quarter"),
-    ];
-
-    for (key, value) in special_cases.iter() {
-        output.push_str(&format!("{}:\n  en: {}\n", key, value));
-    }
-
-    output.push('\n');
-
-    // Units
-    let units = [
-        ("units.singular.foot", "foot"),
-        ("units.singular.kilometer", "kilometer"),
-        ("units.singular.meter", "meter"),
-        ("units.singular.mile", "mile"),
-        ("units.plural.feet", "feet"),
-        ("units.plural.kilometers", "kilometers"),
-        ("units.plural.meters", "meters"),
-        ("units.plural.miles", "miles"),
-    ];
-
-    for (key, value) in units.iter() {
-        output.push_str(&format!("{}:\n  en: {}\n", key, value));
-    }
-
     // Write to file
     let mut file = File::create("./locales/numbers.yml").expect("Failed to create file");
     file.write_all(output.as_bytes())
@@ -0,0 +1,75 @@
+use std::{fs, io, path::PathBuf};
+
+use clap::Parser;
+use osrm_model_extensions::{
+    cli::{EnhanceArgs, write_explanations},
+    response::{enhance_route_response_json, explain_route_response_json},
+};
+use serde_json::Value;
+
+/// Adds banner and voice instructions to a saved route response and writes it to stdout.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// The route response's JSON file, or "-" for stdin.
+    #[arg(default_value = "-")]
+    input: PathBuf,
+
+    /// Print each step's banners and announcements, and why they were chosen, instead.
+    #[arg(long)]
+    explain: bool,
+
+    #[command(flatten)]
+    enhance: EnhanceArgs,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let json = if args.input.as_os_str() == "-" {
+        io::read_to_string(io::stdin())
+    } else {
+        fs::read_to_string(&args.input)
+    }
+    .unwrap_or_else(|error| {
+        exit(format!(
+            "Failed to read {}: {}",
+            args.input.display(),
+            error
+        ))
+    });
+    let mut response: Value = serde_json::from_str(&json).unwrap_or_else(|error| {
+        exit(format!(
+            "Invalid JSON in {}: {}",
+            args.input.display(),
+            error
+        ))
+    });
+    let options = args.enhance.options();
+
+    if args.explain {
+        let explanations = explain_route_response_json(&response, &options)
+            .unwrap_or_else(|error| exit(error.to_string()));
+        write_explanations(&mut io::stdout().lock(), &explanations)
+            .unwrap_or_else(|error| exit(format!("Failed to write: {}", error)));
+        return;
+    }
+
+    let failures = enhance_route_response_json(&mut response, &options)
+        .unwrap_or_else(|error| exit(error.to_string()));
+    for failure in failures {
+        eprintln!(
+            "Route {} was not enhanced: {}",
+            failure.route_index, failure.error
+        );
+    }
+
+    serde_json::to_writer_pretty(io::stdout().lock(), &response)
+        .unwrap_or_else(|error| exit(format!("Failed to write: {}", error)));
+    println!();
+}
+
+fn exit(message: String) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use clap::Parser;
+use osrm_model_extensions::{
+    cli::EnhanceArgs,
+    proxy::{ProxyConfig, serve},
+};
+use tiny_http::Server;
+
+/// Proxies an OSRM compatible server, adding banner and voice instructions to its routes.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Base URL of the upstream server.
+    #[arg(long, default_value = "http://localhost:8002")]
+    upstream: String,
+
+    /// Address to listen on.
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: String,
+
+    /// Number of requests served at once.
+    #[arg(long, default_value_t = 8)]
+    workers: usize,
+
+    /// Seconds an upstream request may take before the proxy answers with a 504.
+    #[arg(long, default_value_t = 30)]
+    timeout: u64,
+
+    #[command(flatten)]
+    enhance: EnhanceArgs,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let config = ProxyConfig {
+        upstream: args.upstream,
+        options: args.enhance.options(),
+        workers: args.workers,
+        timeout: Duration::from_secs(args.timeout),
+    };
+
+    let server = Server::http(&args.listen).unwrap_or_else(|error| {
+        eprintln!("Failed to listen on {}: {}", args.listen, error);
+        std::process::exit(1);
+    });
+    eprintln!("Proxying {} on {}", config.upstream, args.listen);
+
+    serve(server, config);
+}
@@ -0,0 +1,203 @@
+//! Command-line arguments and output shared by the binaries.
+
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+
+use crate::{
+    distance::UnitSystem,
+    locale::Locale,
+    response::{EnhanceJsonOptions, EnhanceOptions, RouteExplanation},
+};
+
+/// Arguments for how routes are enhanced.
+#[derive(Debug, Clone, clap::Args)]
+pub struct EnhanceArgs {
+    /// Units distances are announced in.
+    #[arg(long, value_enum, default_value_t = Units::Metric)]
+    pub units: Units,
+
+    /// Language of the instructions, e.g. "en" or "de-AT".
+    #[arg(long, default_value = "en")]
+    pub locale: Locale,
+
+    /// Precision of the encoded polylines, 5 for OSRM and 6 for Valhalla.
+    #[arg(long, default_value_t = 6)]
+    pub polyline_precision: u32,
+
+    /// Detect each route's polyline precision, using --polyline-precision when it fits.
+    #[arg(long)]
+    pub automatic_polyline_precision: bool,
+
+    /// Announce where the speed limit changes, from the `maxspeed` annotations.
+    #[arg(long)]
+    pub speed_limit_announcements: bool,
+
+    /// Fail routes whose intersections' geometry indices disagree with their step geometry.
+    #[arg(long)]
+    pub validate_intersections: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Units {
+    Metric,
+    ImperialUs,
+    ImperialUk,
+    /// Picked per step from its speed limit unit and country, metric when neither is known.
+    Auto,
+}
+
+impl EnhanceArgs {
+    pub fn options(&self) -> EnhanceJsonOptions {
+        let (unit_system, automatic_unit_system) = match self.units {
+            Units::Metric => (UnitSystem::Metric, false),
+            Units::ImperialUs => (UnitSystem::ImperialUs, false),
+            Units::ImperialUk => (UnitSystem::ImperialUk, false),
+            Units::Auto => (UnitSystem::Metric, true),
+        };
+
+        EnhanceJsonOptions {
+            enhance: EnhanceOptions {
+                polyline_precision: self.polyline_precision,
+                automatic_polyline_precision: self.automatic_polyline_precision,
+                unit_system,
+                locale: self.locale,
+                speed_limit_announcements: self.speed_limit_announcements,
+                ..Default::default()
+            },
+            automatic_unit_system,
+            validate_intersections: self.validate_intersections,
+        }
+    }
+}
+
+/// Writes each step's banners and the announcements considered for it, made or skipped, with
+/// the reasons for them.
+pub fn write_explanations(out: &mut impl Write, routes: &[RouteExplanation]) -> io::Result<()> {
+    for (route_index, route) in routes.iter().enumerate() {
+        let legs = match route {
+            Ok(legs) => legs,
+            Err(error) => {
+                writeln!(out, "Route {}: not enhanced, {}", route_index, error)?;
+                continue;
+            }
+        };
+        writeln!(out, "Route {}", route_index)?;
+
+        for step in legs.iter().flatten() {
+            writeln!(
+                out,
+                "  Leg {} step {}: {} ({:.1} m, {:?})",
+                step.leg_index,
+                step.step_index,
+                step.instruction.as_deref().unwrap_or("no instruction"),
+                step.distance,
+                step.unit_system
+            )?;
+
+            for intersection in &step.intersections {
+                writeln!(
+                    out,
+                    "    Intersection {} at {:.1} m (geometry index {})",
+                    intersection.intersection_index,
+                    intersection.distance_along_step,
+                    intersection.geometry_index
+                )?;
+            }
+
+            for banner in &step.banner_instructions {
+                writeln!(
+                    out,
+                    "    Banner at {:.1} m: {}",
+                    banner.distance_along_geometry, banner.primary.text
+                )?;
+                if let Some(secondary) = &banner.secondary {
+                    writeln!(out, "      Secondary: {}", secondary.text)?;
+                }
+                if let Some(sub) = &banner.sub {
+                    writeln!(out, "      Sub: {}", sub.text)?;
+                }
+            }
+
+            for (decision, instruction) in &step.announcements {
+                match instruction {
+                    Some(instruction) => writeln!(
+                        out,
+                        "    {:?} at {:.1} m: \"{}\", {}",
+                        decision.announcement,
+                        instruction.distance_along_geometry,
+                        instruction.announcement,
+                        decision.reason
+                    )?,
+                    None => writeln!(
+                        out,
+                        "    {:?} skipped: {}",
+                        decision.announcement, decision.reason
+                    )?,
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::explain_route_response_json;
+    use clap::Parser;
+    use insta::assert_snapshot;
+    use serde_json::Value;
+
+    #[derive(Debug, Parser)]
+    struct TestArgs {
+        #[command(flatten)]
+        enhance: EnhanceArgs,
+    }
+
+    #[test]
+    fn test_enhance_args_options() {
+        let args = TestArgs::parse_from([
+            "test",
+            "--units",
+            "auto",
+            "--locale",
+            "de-AT",
+            "--polyline-precision",
+            "5",
+            "--automatic-polyline-precision",
+            "--speed-limit-announcements",
+            "--validate-intersections",
+        ]);
+
+        assert_eq!(
+            args.enhance.options(),
+            EnhanceJsonOptions {
+                enhance: EnhanceOptions {
+                    polyline_precision: 5,
+                    automatic_polyline_precision: true,
+                    locale: Locale::De,
+                    speed_limit_announcements: true,
+                    ..Default::default()
+                },
+                automatic_unit_system: true,
+                validate_intersections: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_write_explanations() {
+        let response: Value = serde_json::from_str(
+            &std::fs::read_to_string("./fixtures/valhalla-short.json").unwrap(),
+        )
+        .unwrap();
+        let explanations =
+            explain_route_response_json(&response, &EnhanceJsonOptions::default()).unwrap();
+
+        let mut out = Vec::new();
+        write_explanations(&mut out, &explanations).unwrap();
+        assert_snapshot!(String::from_utf8(out).unwrap());
+    }
+}
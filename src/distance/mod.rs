@@ -1,5 +1,14 @@
-use spoken_unit::{SpokenFoot, SpokenKilometer, SpokenMeter, SpokenMile, SpokenUnit};
+use std::fmt;
 
+use round::round;
+
+use crate::{locale::Locale, osrm::models::ExtendedRouteLeg};
+use spoken_unit::{
+    Gender, SpokenFoot, SpokenKilometer, SpokenMeter, SpokenMile, SpokenUnit, SpokenYard,
+};
+
+pub mod number_speller;
+pub mod plural;
 pub mod spoken_distance;
 pub mod spoken_numbers;
 pub mod spoken_unit;
@@ -10,15 +19,113 @@ pub enum Unit {
     Kilometers,
     Miles,
     Feet,
+    Yards,
 }
 
 impl Unit {
-    pub fn spoken_form(&self, count: f64) -> String {
+    /// The unit's abbreviation, e.g. "km".
+    pub fn symbol(&self) -> &'static str {
         match self {
-            Unit::Meters => SpokenMeter::get_form(count),
-            Unit::Kilometers => SpokenKilometer::get_form(count),
-            Unit::Miles => SpokenMile::get_form(count),
-            Unit::Feet => SpokenFoot::get_form(count),
+            Unit::Meters => "m",
+            Unit::Kilometers => "km",
+            Unit::Miles => "mi",
+            Unit::Feet => "ft",
+            Unit::Yards => "yd",
+        }
+    }
+
+    pub fn spoken_form(&self, count: f64, locale: Locale) -> String {
+        match self {
+            Unit::Meters => SpokenMeter::get_form(count, locale),
+            Unit::Kilometers => SpokenKilometer::get_form(count, locale),
+            Unit::Miles => SpokenMile::get_form(count, locale),
+            Unit::Feet => SpokenFoot::get_form(count, locale),
+            Unit::Yards => SpokenYard::get_form(count, locale),
+        }
+    }
+
+    /// The grammatical gender of the unit's name in `locale`.
+    pub fn gender(&self, locale: Locale) -> Gender {
+        match self {
+            Unit::Meters => SpokenMeter::gender(locale),
+            Unit::Kilometers => SpokenKilometer::gender(locale),
+            Unit::Miles => SpokenMile::gender(locale),
+            Unit::Feet => SpokenFoot::gender(locale),
+            Unit::Yards => SpokenYard::gender(locale),
+        }
+    }
+}
+
+/// The units distances are announced in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnitSystem {
+    /// Kilometers and meters.
+    #[default]
+    Metric,
+
+    /// Miles and feet.
+    ImperialUs,
+
+    /// Miles and yards, as used in the United Kingdom.
+    ImperialUk,
+}
+
+impl UnitSystem {
+    /// The unit of long distances and of the announcement thresholds.
+    pub fn long_unit(&self) -> Unit {
+        match self {
+            UnitSystem::Metric => Unit::Kilometers,
+            UnitSystem::ImperialUs | UnitSystem::ImperialUk => Unit::Miles,
+        }
+    }
+
+    /// The unit of distances shorter than a quarter of the long unit.
+    pub fn short_unit(&self) -> Unit {
+        match self {
+            UnitSystem::Metric => Unit::Meters,
+            UnitSystem::ImperialUs => Unit::Feet,
+            UnitSystem::ImperialUk => Unit::Yards,
+        }
+    }
+
+    /// The unit system road distances are signed in within a country, by ISO 3166-1 alpha-2
+    /// or alpha-3 code.
+    pub fn for_country(code: &str) -> UnitSystem {
+        match code.to_uppercase().as_str() {
+            "US" | "USA" | "LR" | "LBR" | "MM" | "MMR" | "PR" | "PRI" | "GU" | "GUM" | "VI"
+            | "VIR" | "AS" | "ASM" | "MP" | "MNP" => UnitSystem::ImperialUs,
+            "GB" | "GBR" | "IM" | "IMN" | "JE" | "JEY" | "GG" | "GGY" => UnitSystem::ImperialUk,
+            _ => UnitSystem::Metric,
+        }
+    }
+
+    /// The unit system of the step at `step_index` of `leg`, or `None` when neither its speed
+    /// limit unit nor its country is known.
+    ///
+    /// The step's speed limit unit decides between miles and kilometers, so a leg crossing a
+    /// border is announced in the units signed on each side of it. The step's country, from its
+    /// first intersection's admin or else the leg's first, then tells US from UK miles, and
+    /// decides alone when the step carries no speed limit unit.
+    pub fn for_step(leg: &ExtendedRouteLeg, step_index: usize) -> Option<UnitSystem> {
+        let step = leg.steps.as_ref()?.get(step_index)?;
+        let admins = leg.admins.as_deref().unwrap_or_default();
+        let admin = step
+            .intersections
+            .iter()
+            .flatten()
+            .next()
+            .and_then(|intersection| intersection.admin_index)
+            .and_then(|admin_index| admins.get(admin_index))
+            .or(admins.first());
+        let country = admin
+            .and_then(|admin| admin.country_code())
+            .map(UnitSystem::for_country);
+
+        match step.speed_limit_unit.as_deref() {
+            Some("mph") if country == Some(UnitSystem::ImperialUk) => Some(UnitSystem::ImperialUk),
+            Some("mph") => Some(UnitSystem::ImperialUs),
+            Some("km/h") => Some(UnitSystem::Metric),
+            _ => country,
         }
     }
 }
@@ -50,6 +157,10 @@ impl Distance {
         Distance::new(value, Unit::Feet)
     }
 
+    pub fn from_yards(value: f64) -> Self {
+        Distance::new(value, Unit::Yards)
+    }
+
     pub fn to(&self, target_unit: Unit) -> Distance {
         // First convert to meters as base unit
         let meters = match self.unit {
@@ -57,6 +168,7 @@ impl Distance {
             Unit::Kilometers => self.value * 1000.0,
             Unit::Miles => self.value * 1609.344,
             Unit::Feet => self.value * 0.3048,
+            Unit::Yards => self.value * 0.9144,
         };
 
         // Then convert from meters to target unit and return new Distance
@@ -65,6 +177,7 @@ impl Distance {
             Unit::Kilometers => meters / 1000.0,
             Unit::Miles => meters / 1609.344,
             Unit::Feet => meters / 0.3048,
+            Unit::Yards => meters / 0.9144,
         };
 
         Distance {
@@ -85,8 +198,14 @@ impl Distance {
         matches!(self.unit, Unit::Meters | Unit::Kilometers)
     }
 
-    pub fn spoken_unit(&self) -> String {
-        self.unit.spoken_form(self.value)
+    pub fn spoken_unit(&self, locale: Locale) -> String {
+        self.unit.spoken_form(self.value, locale)
+    }
+}
+
+impl fmt::Display for Distance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", round(self.value, 2), self.unit.symbol())
     }
 }
 
@@ -118,6 +237,7 @@ impl Distance {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::osrm::models::{Admin, ExtendedIntersection, ExtendedRouteStep};
     use rust_i18n::t;
 
     #[test]
@@ -127,6 +247,71 @@ mod tests {
         assert_eq!(distance.to(Unit::Meters).value, 1000.0);
         assert!((distance.to(Unit::Miles).value - 0.621371).abs() < 0.000001);
         assert!((distance.to(Unit::Feet).value - 3280.84).abs() < 0.01);
+        assert!((distance.to(Unit::Yards).value - 1093.61).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_unit_system_for_country() {
+        assert_eq!(UnitSystem::for_country("US"), UnitSystem::ImperialUs);
+        assert_eq!(UnitSystem::for_country("usa"), UnitSystem::ImperialUs);
+        assert_eq!(UnitSystem::for_country("GBR"), UnitSystem::ImperialUk);
+        assert_eq!(UnitSystem::for_country("CA"), UnitSystem::Metric);
+        assert_eq!(UnitSystem::for_country("IE"), UnitSystem::Metric);
+    }
+
+    #[test]
+    fn test_unit_system_for_step() {
+        let admin = |code: &str| Admin {
+            iso_3166_1: Some(code.to_string()),
+            iso_3166_1_alpha3: None,
+        };
+        let step = |unit: Option<&str>, admin_index: Option<usize>| ExtendedRouteStep {
+            speed_limit_unit: unit.map(str::to_string),
+            intersections: Some(vec![ExtendedIntersection {
+                admin_index,
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+
+        // Crossing from the US into Canada, each step keeps the units signed where it is.
+        let border = ExtendedRouteLeg {
+            admins: Some(vec![admin("US"), admin("CA")]),
+            steps: Some(vec![
+                step(Some("mph"), Some(0)),
+                step(Some("km/h"), Some(1)),
+                step(None, Some(0)),
+                step(None, Some(1)),
+            ]),
+        };
+        assert_eq!(
+            UnitSystem::for_step(&border, 0),
+            Some(UnitSystem::ImperialUs)
+        );
+        assert_eq!(UnitSystem::for_step(&border, 1), Some(UnitSystem::Metric));
+        assert_eq!(
+            UnitSystem::for_step(&border, 2),
+            Some(UnitSystem::ImperialUs)
+        );
+        assert_eq!(UnitSystem::for_step(&border, 3), Some(UnitSystem::Metric));
+
+        let uk = ExtendedRouteLeg {
+            admins: Some(vec![admin("GB")]),
+            steps: Some(vec![step(Some("mph"), None)]),
+        };
+        assert_eq!(UnitSystem::for_step(&uk, 0), Some(UnitSystem::ImperialUk));
+
+        let countries_only = ExtendedRouteLeg {
+            admins: Some(vec![admin("US")]),
+            steps: Some(vec![ExtendedRouteStep::default()]),
+        };
+        assert_eq!(
+            UnitSystem::for_step(&countries_only, 0),
+            Some(UnitSystem::ImperialUs)
+        );
+
+        assert_eq!(UnitSystem::for_step(&countries_only, 1), None);
+        assert_eq!(UnitSystem::for_step(&ExtendedRouteLeg::default(), 0), None);
     }
 
     #[test]
@@ -140,7 +325,11 @@ mod tests {
         let single_meter = Distance::new(1.0, Unit::Meters);
         let multiple_meters = Distance::new(2.0, Unit::Meters);
 
-        assert_eq!(single_meter.spoken_unit(), t!("units.singular.meter"));
-        assert_eq!(multiple_meters.spoken_unit(), t!("units.plural.meters"));
+        assert_eq!(single_meter.spoken_unit(Locale::En), t!("units.meter.one"));
+        assert_eq!(
+            multiple_meters.spoken_unit(Locale::En),
+            t!("units.meter.other")
+        );
+        assert_eq!(multiple_meters.spoken_unit(Locale::Fr), "mètres");
     }
 }
@@ -0,0 +1,692 @@
+use crate::{distance::spoken_unit::Gender, locale::Locale};
+
+/// Spells whole numbers as words following one language's composition rules.
+pub trait NumberSpeller {
+    /// Spells a non-negative whole number, e.g. 21 as "twenty one".
+    fn spell(&self, num: u64) -> String;
+
+    /// Spells a number counting a noun of the given gender, e.g. "un" and "una" in Spanish
+    /// where the number alone is "uno".
+    fn spell_before_noun(&self, num: u64, _gender: Gender) -> String {
+        self.spell(num)
+    }
+
+    /// The word preceding negative numbers.
+    fn negative(&self) -> &'static str;
+
+    /// Spells a whole number, including negative numbers.
+    fn spell_signed(&self, num: i64) -> String {
+        if num < 0 {
+            format!("{} {}", self.negative(), self.spell(num.unsigned_abs()))
+        } else {
+            self.spell(num as u64)
+        }
+    }
+}
+
+/// The number speller for a locale.
+pub fn number_speller(locale: Locale) -> &'static dyn NumberSpeller {
+    match locale {
+        Locale::En => &EnglishNumberSpeller,
+        Locale::De => &GermanNumberSpeller,
+        Locale::Fr => &FrenchNumberSpeller,
+        Locale::Es => &SpanishNumberSpeller,
+    }
+}
+
+/// Splits a number into groups of three digits, least significant first.
+fn groups_of_thousand(mut num: u64) -> Vec<u64> {
+    let mut groups = vec![num % 1000];
+    num /= 1000;
+    while num > 0 {
+        groups.push(num % 1000);
+        num /= 1000;
+    }
+    groups
+}
+
+#[derive(Debug, Clone)]
+pub struct EnglishNumberSpeller;
+
+impl EnglishNumberSpeller {
+    const ONES: [&'static str; 20] = [
+        "zero",
+        "one",
+        "two",
+        "three",
+        "four",
+        "five",
+        "six",
+        "seven",
+        "eight",
+        "nine",
+        "ten",
+        "eleven",
+        "twelve",
+        "thirteen",
+        "fourteen",
+        "fifteen",
+        "sixteen",
+        "seventeen",
+        "eighteen",
+        "nineteen",
+    ];
+    const TENS: [&'static str; 10] = [
+        "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+    ];
+    const SCALES: [&'static str; 7] = [
+        "",
+        "thousand",
+        "million",
+        "billion",
+        "trillion",
+        "quadrillion",
+        "quintillion",
+    ];
+
+    fn below_thousand(n: u64) -> String {
+        let mut words = Vec::new();
+        if n >= 100 {
+            words.push(format!("{} hundred", Self::ONES[(n / 100) as usize]));
+        }
+        match n % 100 {
+            0 => {}
+            rest @ 1..20 => words.push(Self::ONES[rest as usize].to_string()),
+            rest => {
+                words.push(Self::TENS[(rest / 10) as usize].to_string());
+                if rest % 10 > 0 {
+                    words.push(Self::ONES[(rest % 10) as usize].to_string());
+                }
+            }
+        }
+        words.join(" ")
+    }
+}
+
+impl NumberSpeller for EnglishNumberSpeller {
+    fn spell(&self, num: u64) -> String {
+        if num == 0 {
+            return Self::ONES[0].to_string();
+        }
+
+        groups_of_thousand(num)
+            .into_iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, group)| *group > 0)
+            .map(|(scale, group)| match scale {
+                0 => Self::below_thousand(group),
+                _ => format!("{} {}", Self::below_thousand(group), Self::SCALES[scale]),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn negative(&self) -> &'static str {
+        "negative"
+    }
+}
+
+/// German compounds numbers below a million into one word ("einundzwanzig",
+/// "zweitausendeins") and writes "ein" rather than "eins" inside compounds.
+#[derive(Debug, Clone)]
+pub struct GermanNumberSpeller;
+
+impl GermanNumberSpeller {
+    const ONES: [&'static str; 20] = [
+        "null",
+        "eins",
+        "zwei",
+        "drei",
+        "vier",
+        "fünf",
+        "sechs",
+        "sieben",
+        "acht",
+        "neun",
+        "zehn",
+        "elf",
+        "zwölf",
+        "dreizehn",
+        "vierzehn",
+        "fünfzehn",
+        "sechzehn",
+        "siebzehn",
+        "achtzehn",
+        "neunzehn",
+    ];
+    const TENS: [&'static str; 10] = [
+        "", "", "zwanzig", "dreißig", "vierzig", "fünfzig", "sechzig", "siebzig", "achtzig",
+        "neunzig",
+    ];
+    /// Singular and plural names of each power of a thousand from a million up (long scale).
+    const SCALES: [(&'static str, &'static str); 5] = [
+        ("Million", "Millionen"),
+        ("Milliarde", "Milliarden"),
+        ("Billion", "Billionen"),
+        ("Billiarde", "Billiarden"),
+        ("Trillion", "Trillionen"),
+    ];
+
+    /// Spells 1..999. `standalone` spells a trailing one as "eins" instead of "ein".
+    fn below_thousand(n: u64, standalone: bool) -> String {
+        let mut word = String::new();
+        if n >= 100 {
+            word.push_str(Self::unit(n / 100, false));
+            word.push_str("hundert");
+        }
+        match n % 100 {
+            0 => {}
+            rest @ 1..20 => word.push_str(Self::unit(rest, standalone)),
+            rest => {
+                if rest % 10 > 0 {
+                    word.push_str(Self::unit(rest % 10, false));
+                    word.push_str("und");
+                }
+                word.push_str(Self::TENS[(rest / 10) as usize]);
+            }
+        }
+        word
+    }
+
+    fn unit(n: u64, standalone: bool) -> &'static str {
+        if n == 1 && !standalone {
+            "ein"
+        } else {
+            Self::ONES[n as usize]
+        }
+    }
+}
+
+impl NumberSpeller for GermanNumberSpeller {
+    /// "ein Kilometer", "eine Meile", "hundertein Meter".
+    fn spell_before_noun(&self, num: u64, gender: Gender) -> String {
+        match (num, gender) {
+            (1, Gender::Feminine) => "eine".to_string(),
+            (num, _) if num % 100 == 1 => self.spell(num).trim_end_matches('s').to_string(),
+            (num, _) => self.spell(num),
+        }
+    }
+
+    fn spell(&self, num: u64) -> String {
+        if num == 0 {
+            return Self::ONES[0].to_string();
+        }
+
+        let groups = groups_of_thousand(num);
+        let mut words = Vec::new();
+
+        for (scale, group) in groups.iter().enumerate().skip(2).rev() {
+            let (singular, plural) = Self::SCALES[scale - 2];
+            match group {
+                0 => {}
+                1 => words.push(format!("eine {}", singular)),
+                n => words.push(format!("{} {}", Self::below_thousand(*n, false), plural)),
+            }
+        }
+
+        let thousands = groups.get(1).copied().unwrap_or(0);
+        let ones = groups[0];
+        let mut below_million = String::new();
+        if thousands > 0 {
+            below_million.push_str(&Self::below_thousand(thousands, false));
+            below_million.push_str("tausend");
+        }
+        if ones > 0 {
+            below_million.push_str(&Self::below_thousand(ones, true));
+        }
+        if !below_million.is_empty() {
+            words.push(below_million);
+        }
+
+        words.join(" ")
+    }
+
+    fn negative(&self) -> &'static str {
+        "minus"
+    }
+}
+
+/// French counts in twenties from seventy ("soixante-dix", "quatre-vingt-dix"), joins
+/// "et un" to the tens up to seventy, and pluralizes "cent" and "quatre-vingt" only at
+/// the end of a number or before a noun such as "millions".
+#[derive(Debug, Clone)]
+pub struct FrenchNumberSpeller;
+
+impl FrenchNumberSpeller {
+    const ONES: [&'static str; 20] = [
+        "zéro", "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf", "dix",
+        "onze", "douze", "treize", "quatorze", "quinze", "seize", "dix-sept", "dix-huit",
+        "dix-neuf",
+    ];
+    const TENS: [&'static str; 7] = [
+        "",
+        "",
+        "vingt",
+        "trente",
+        "quarante",
+        "cinquante",
+        "soixante",
+    ];
+    /// Singular and plural names of each power of a thousand from a million up (long scale).
+    const SCALES: [(&'static str, &'static str); 5] = [
+        ("million", "millions"),
+        ("milliard", "milliards"),
+        ("billion", "billions"),
+        ("billiard", "billiards"),
+        ("trillion", "trillions"),
+    ];
+
+    fn below_hundred(n: u64, plural: bool) -> String {
+        match n {
+            0..20 => Self::ONES[n as usize].to_string(),
+            80 if plural => "quatre-vingts".to_string(),
+            80..100 => match n - 80 {
+                0 => "quatre-vingt".to_string(),
+                rest => format!("quatre-vingt-{}", Self::ONES[rest as usize]),
+            },
+            60..80 => match n - 60 {
+                11 => "soixante et onze".to_string(),
+                rest => format!("soixante-{}", Self::ONES[rest as usize]),
+            },
+            _ => {
+                let tens = Self::TENS[(n / 10) as usize];
+                match n % 10 {
+                    0 => tens.to_string(),
+                    1 => format!("{} et un", tens),
+                    ones => format!("{}-{}", tens, Self::ONES[ones as usize]),
+                }
+            }
+        }
+    }
+
+    /// Spells 1..999. `plural` allows the plural "cents" and "quatre-vingts".
+    fn below_thousand(n: u64, plural: bool) -> String {
+        let (hundreds, rest) = (n / 100, n % 100);
+        let hundreds = match hundreds {
+            0 => None,
+            1 => Some("cent".to_string()),
+            h if rest == 0 && plural => Some(format!("{} cents", Self::ONES[h as usize])),
+            h => Some(format!("{} cent", Self::ONES[h as usize])),
+        };
+        let rest = (rest > 0).then(|| Self::below_hundred(rest, plural));
+
+        [hundreds, rest]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl NumberSpeller for FrenchNumberSpeller {
+    /// "vingt et une" before a feminine noun.
+    fn spell_before_noun(&self, num: u64, gender: Gender) -> String {
+        let words = self.spell(num);
+        match words.strip_suffix("un") {
+            Some(prefix) if gender == Gender::Feminine && num % 10 == 1 => format!("{}une", prefix),
+            _ => words,
+        }
+    }
+
+    fn spell(&self, num: u64) -> String {
+        if num == 0 {
+            return Self::ONES[0].to_string();
+        }
+
+        let groups = groups_of_thousand(num);
+        let mut words = Vec::new();
+
+        for (scale, group) in groups.iter().enumerate().skip(2).rev() {
+            let (singular, plural) = Self::SCALES[scale - 2];
+            match group {
+                0 => {}
+                1 => words.push(format!("un {}", singular)),
+                n => words.push(format!("{} {}", Self::below_thousand(*n, true), plural)),
+            }
+        }
+
+        match groups.get(1).copied().unwrap_or(0) {
+            0 => {}
+            1 => words.push("mille".to_string()),
+            n => words.push(format!("{} mille", Self::below_thousand(n, false))),
+        }
+
+        if groups[0] > 0 {
+            words.push(Self::below_thousand(groups[0], true));
+        }
+
+        words.join(" ")
+    }
+
+    fn negative(&self) -> &'static str {
+        "moins"
+    }
+}
+
+/// Spanish groups digits by millions ("mil millones" for a billion), has irregular
+/// hundreds ("quinientos") and shortens "uno" to "un" before "mil" and "millones".
+#[derive(Debug, Clone)]
+pub struct SpanishNumberSpeller;
+
+impl SpanishNumberSpeller {
+    const ONES: [&'static str; 30] = [
+        "cero",
+        "uno",
+        "dos",
+        "tres",
+        "cuatro",
+        "cinco",
+        "seis",
+        "siete",
+        "ocho",
+        "nueve",
+        "diez",
+        "once",
+        "doce",
+        "trece",
+        "catorce",
+        "quince",
+        "dieciséis",
+        "diecisiete",
+        "dieciocho",
+        "diecinueve",
+        "veinte",
+        "veintiuno",
+        "veintidós",
+        "veintitrés",
+        "veinticuatro",
+        "veinticinco",
+        "veintiséis",
+        "veintisiete",
+        "veintiocho",
+        "veintinueve",
+    ];
+    const TENS: [&'static str; 10] = [
+        "",
+        "",
+        "",
+        "treinta",
+        "cuarenta",
+        "cincuenta",
+        "sesenta",
+        "setenta",
+        "ochenta",
+        "noventa",
+    ];
+    const HUNDREDS: [&'static str; 10] = [
+        "",
+        "ciento",
+        "doscientos",
+        "trescientos",
+        "cuatrocientos",
+        "quinientos",
+        "seiscientos",
+        "setecientos",
+        "ochocientos",
+        "novecientos",
+    ];
+    /// Singular and plural names of each power of a million.
+    const SCALES: [(&'static str, &'static str); 3] = [
+        ("millón", "millones"),
+        ("billón", "billones"),
+        ("trillón", "trillones"),
+    ];
+
+    fn below_thousand(n: u64) -> String {
+        if n == 100 {
+            return "cien".to_string();
+        }
+
+        let hundreds = (n >= 100).then(|| Self::HUNDREDS[(n / 100) as usize].to_string());
+        let rest = match n % 100 {
+            0 => None,
+            rest @ 1..30 => Some(Self::ONES[rest as usize].to_string()),
+            rest => Some(match rest % 10 {
+                0 => Self::TENS[(rest / 10) as usize].to_string(),
+                ones => format!(
+                    "{} y {}",
+                    Self::TENS[(rest / 10) as usize],
+                    Self::ONES[ones as usize]
+                ),
+            }),
+        };
+
+        [hundreds, rest]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Spells 1..999,999.
+    fn below_million(n: u64) -> String {
+        let (thousands, ones) = (n / 1000, n % 1000);
+        let thousands = match thousands {
+            0 => None,
+            1 => Some("mil".to_string()),
+            t => Some(format!("{} mil", apocope(Self::below_thousand(t)))),
+        };
+        let ones = (ones > 0).then(|| Self::below_thousand(ones));
+
+        [thousands, ones]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Shortens a trailing "uno" before a noun: "veintiuno" to "veintiún", "treinta y uno" to "treinta y un".
+fn apocope(words: String) -> String {
+    if let Some(prefix) = words.strip_suffix("veintiuno") {
+        format!("{}veintiún", prefix)
+    } else if let Some(prefix) = words.strip_suffix("uno") {
+        format!("{}un", prefix)
+    } else {
+        words
+    }
+}
+
+impl NumberSpeller for SpanishNumberSpeller {
+    /// "un kilómetro", "veintiún kilómetros", "una milla", "veintiuna millas", "doscientas millas".
+    fn spell_before_noun(&self, num: u64, gender: Gender) -> String {
+        let words = self.spell(num);
+        if gender != Gender::Feminine {
+            return apocope(words);
+        }
+
+        // The hundreds agree with the noun too, but not those counting the masculine "millones".
+        let words = words.split(' ').collect::<Vec<_>>();
+        let below_million = words
+            .iter()
+            .rposition(|word| {
+                Self::SCALES
+                    .iter()
+                    .any(|(one, many)| word == one || word == many)
+            })
+            .map_or(0, |index| index + 1);
+        let words = words
+            .iter()
+            .enumerate()
+            .map(|(index, word)| match word.strip_suffix("ientos") {
+                Some(prefix) if index >= below_million => format!("{}ientas", prefix),
+                _ => word.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        match words.strip_suffix("uno") {
+            Some(prefix) => format!("{}una", prefix),
+            None => words,
+        }
+    }
+
+    fn spell(&self, num: u64) -> String {
+        if num == 0 {
+            return Self::ONES[0].to_string();
+        }
+
+        let mut groups = Vec::new();
+        let mut rest = num;
+        while rest > 0 {
+            groups.push(rest % 1_000_000);
+            rest /= 1_000_000;
+        }
+
+        groups
+            .into_iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, group)| *group > 0)
+            .map(|(scale, group)| match (scale, group) {
+                (0, group) => Self::below_million(group),
+                (scale, 1) => format!("un {}", Self::SCALES[scale - 1].0),
+                (scale, group) => format!(
+                    "{} {}",
+                    apocope(Self::below_million(group)),
+                    Self::SCALES[scale - 1].1
+                ),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn negative(&self) -> &'static str {
+        "menos"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spell(locale: Locale, num: u64) -> String {
+        number_speller(locale).spell(num)
+    }
+
+    #[test]
+    fn test_english() {
+        assert_eq!(spell(Locale::En, 21), "twenty one");
+        assert_eq!(spell(Locale::En, 1_000_001), "one million one");
+        assert_eq!(
+            spell(Locale::En, 2_500_000_000),
+            "two billion five hundred million"
+        );
+    }
+
+    #[test]
+    fn test_german() {
+        assert_eq!(spell(Locale::De, 1), "eins");
+        assert_eq!(spell(Locale::De, 21), "einundzwanzig");
+        assert_eq!(spell(Locale::De, 101), "einhunderteins");
+        assert_eq!(spell(Locale::De, 1000), "eintausend");
+        assert_eq!(
+            spell(Locale::De, 21_345),
+            "einundzwanzigtausenddreihundertfünfundvierzig"
+        );
+        assert_eq!(
+            spell(Locale::De, 1_200_000),
+            "eine Million zweihunderttausend"
+        );
+        assert_eq!(spell(Locale::De, 3_000_000_000), "drei Milliarden");
+    }
+
+    #[test]
+    fn test_french() {
+        assert_eq!(spell(Locale::Fr, 21), "vingt et un");
+        assert_eq!(spell(Locale::Fr, 71), "soixante et onze");
+        assert_eq!(spell(Locale::Fr, 77), "soixante-dix-sept");
+        assert_eq!(spell(Locale::Fr, 80), "quatre-vingts");
+        assert_eq!(spell(Locale::Fr, 81), "quatre-vingt-un");
+        assert_eq!(spell(Locale::Fr, 90), "quatre-vingt-dix");
+        assert_eq!(spell(Locale::Fr, 200), "deux cents");
+        assert_eq!(spell(Locale::Fr, 201), "deux cent un");
+        assert_eq!(spell(Locale::Fr, 1000), "mille");
+        assert_eq!(spell(Locale::Fr, 200_000), "deux cent mille");
+        assert_eq!(spell(Locale::Fr, 80_000_000), "quatre-vingts millions");
+        assert_eq!(spell(Locale::Fr, 1_000_000_000), "un milliard");
+    }
+
+    #[test]
+    fn test_spanish() {
+        assert_eq!(spell(Locale::Es, 21), "veintiuno");
+        assert_eq!(spell(Locale::Es, 31), "treinta y uno");
+        assert_eq!(spell(Locale::Es, 100), "cien");
+        assert_eq!(spell(Locale::Es, 101), "ciento uno");
+        assert_eq!(spell(Locale::Es, 500), "quinientos");
+        assert_eq!(spell(Locale::Es, 1000), "mil");
+        assert_eq!(spell(Locale::Es, 21_000), "veintiún mil");
+        assert_eq!(spell(Locale::Es, 1_000_000), "un millón");
+        assert_eq!(spell(Locale::Es, 31_000_000), "treinta y un millones");
+        assert_eq!(spell(Locale::Es, 1_500_000_000), "mil quinientos millones");
+    }
+
+    #[test]
+    fn test_spell_before_noun() {
+        let spell_before_noun =
+            |locale, num, gender| number_speller(locale).spell_before_noun(num, gender);
+
+        assert_eq!(spell_before_noun(Locale::En, 1, Gender::Neuter), "one");
+        assert_eq!(spell_before_noun(Locale::De, 1, Gender::Masculine), "ein");
+        assert_eq!(spell_before_noun(Locale::De, 1, Gender::Feminine), "eine");
+        assert_eq!(
+            spell_before_noun(Locale::De, 101, Gender::Masculine),
+            "einhundertein"
+        );
+        assert_eq!(
+            spell_before_noun(Locale::De, 21, Gender::Masculine),
+            "einundzwanzig"
+        );
+        assert_eq!(
+            spell_before_noun(Locale::Fr, 21, Gender::Feminine),
+            "vingt et une"
+        );
+        assert_eq!(spell_before_noun(Locale::Fr, 1, Gender::Masculine), "un");
+        assert_eq!(spell_before_noun(Locale::Es, 1, Gender::Masculine), "un");
+        assert_eq!(
+            spell_before_noun(Locale::Es, 21, Gender::Masculine),
+            "veintiún"
+        );
+        assert_eq!(
+            spell_before_noun(Locale::Es, 21, Gender::Feminine),
+            "veintiuna"
+        );
+        assert_eq!(
+            spell_before_noun(Locale::Es, 31, Gender::Feminine),
+            "treinta y una"
+        );
+        assert_eq!(
+            spell_before_noun(Locale::Es, 200, Gender::Feminine),
+            "doscientas"
+        );
+        assert_eq!(
+            spell_before_noun(Locale::Es, 500, Gender::Feminine),
+            "quinientas"
+        );
+        assert_eq!(
+            spell_before_noun(Locale::Es, 201, Gender::Feminine),
+            "doscientas una"
+        );
+        assert_eq!(
+            spell_before_noun(Locale::Es, 500, Gender::Masculine),
+            "quinientos"
+        );
+        assert_eq!(
+            spell_before_noun(Locale::Es, 300_500_000, Gender::Feminine),
+            "trescientos millones quinientas mil"
+        );
+    }
+
+    #[test]
+    fn test_negative() {
+        assert_eq!(number_speller(Locale::En).spell_signed(-5), "negative five");
+        assert_eq!(number_speller(Locale::De).spell_signed(-5), "minus fünf");
+        assert_eq!(
+            number_speller(Locale::Es).spell_signed(i64::MIN),
+            "menos nueve trillones doscientos veintitrés mil trescientos setenta y dos billones \
+             treinta y seis mil ochocientos cincuenta y cuatro millones setecientos setenta y \
+             cinco mil ochocientos ocho"
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use crate::locale::Locale;
+
+/// A CLDR plural category, selecting the form of a word that follows a quantity.
+///
+/// See <https://cldr.unicode.org/index/cldr-spec/plural-rules>.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    /// The category of `count` under the cardinal plural rules of `locale`.
+    pub fn for_count(count: f64, locale: Locale) -> Self {
+        let n = count.abs();
+        // CLDR operands: `i` is the integer part, `v` whether there are visible fraction digits.
+        let i = n.trunc() as u64;
+        let v = n.fract() != 0.0;
+
+        match locale {
+            Locale::En | Locale::De => {
+                if i == 1 && !v {
+                    PluralCategory::One
+                } else {
+                    PluralCategory::Other
+                }
+            }
+            Locale::Fr => {
+                if i == 0 || i == 1 {
+                    PluralCategory::One
+                } else if is_many_millions(i, v) {
+                    PluralCategory::Many
+                } else {
+                    PluralCategory::Other
+                }
+            }
+            Locale::Es => {
+                if n == 1.0 {
+                    PluralCategory::One
+                } else if is_many_millions(i, v) {
+                    PluralCategory::Many
+                } else {
+                    PluralCategory::Other
+                }
+            }
+        }
+    }
+
+    /// The key suffix used for the category in `locales/*.yml`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PluralCategory::Zero => "zero",
+            PluralCategory::One => "one",
+            PluralCategory::Two => "two",
+            PluralCategory::Few => "few",
+            PluralCategory::Many => "many",
+            PluralCategory::Other => "other",
+        }
+    }
+}
+
+/// French and Spanish treat exact multiples of a million as a noun taking "de" ("un
+/// million de mètres").
+fn is_many_millions(i: u64, v: bool) -> bool {
+    i != 0 && i.is_multiple_of(1_000_000) && !v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_english() {
+        assert_eq!(
+            PluralCategory::for_count(1.0, Locale::En),
+            PluralCategory::One
+        );
+        assert_eq!(
+            PluralCategory::for_count(0.0, Locale::En),
+            PluralCategory::Other
+        );
+        assert_eq!(
+            PluralCategory::for_count(1.5, Locale::En),
+            PluralCategory::Other
+        );
+        assert_eq!(
+            PluralCategory::for_count(2.0, Locale::En),
+            PluralCategory::Other
+        );
+    }
+
+    #[test]
+    fn test_french() {
+        assert_eq!(
+            PluralCategory::for_count(0.0, Locale::Fr),
+            PluralCategory::One
+        );
+        assert_eq!(
+            PluralCategory::for_count(1.5, Locale::Fr),
+            PluralCategory::One
+        );
+        assert_eq!(
+            PluralCategory::for_count(2.0, Locale::Fr),
+            PluralCategory::Other
+        );
+        assert_eq!(
+            PluralCategory::for_count(2_000_000.0, Locale::Fr),
+            PluralCategory::Many
+        );
+    }
+
+    #[test]
+    fn test_spanish() {
+        assert_eq!(
+            PluralCategory::for_count(1.0, Locale::Es),
+            PluralCategory::One
+        );
+        assert_eq!(
+            PluralCategory::for_count(1.5, Locale::Es),
+            PluralCategory::Other
+        );
+        assert_eq!(
+            PluralCategory::for_count(1_000_000.0, Locale::Es),
+            PluralCategory::Many
+        );
+    }
+}
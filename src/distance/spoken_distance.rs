@@ -1,53 +1,64 @@
 use round::round;
+use rust_i18n::t;
 
-use crate::distance::spoken_numbers::number_to_words;
-use crate::distance::{Distance, Unit};
+use crate::distance::number_speller::number_speller;
+use crate::distance::spoken_numbers::Fraction;
+use crate::distance::{Distance, Unit, UnitSystem};
+use crate::locale::Locale;
 use std::fmt;
 
+/// Kilometers and miles below this are rounded to the nearest half.
+const MAXIMUM_HALVES: f64 = 10.0;
+
+/// A distance as it is spoken in an instruction, e.g. "half a mile".
+///
+/// German distances are in the dative, as instructions say them after "In" or "auf".
 pub struct SpokenDistance {
     distance: Distance,
-    is_metric: bool,
+    unit_system: UnitSystem,
+    locale: Locale,
 }
 
 impl SpokenDistance {
     pub fn from_distance(distance: Distance) -> Self {
         SpokenDistance {
             distance,
-            is_metric: distance.is_metric(),
+            unit_system: match distance.unit() {
+                Unit::Meters | Unit::Kilometers => UnitSystem::Metric,
+                Unit::Miles | Unit::Feet => UnitSystem::ImperialUs,
+                Unit::Yards => UnitSystem::ImperialUk,
+            },
+            locale: Locale::default(),
         }
     }
 
-    pub fn from_meters(value: f64, metric: bool) -> Self {
+    pub fn from_meters(value: f64, unit_system: UnitSystem) -> Self {
         SpokenDistance {
             distance: Distance::from_meters(value),
-            is_metric: metric,
+            unit_system,
+            locale: Locale::default(),
         }
     }
 
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
     fn converted(&self) -> Distance {
-        if self.is_metric {
-            let km = self.distance.to(Unit::Kilometers);
-            if km.value() < 0.25 {
-                self.distance.to(Unit::Meters)
-            } else {
-                km
-            }
+        let long = self.distance.to(self.unit_system.long_unit());
+        if long.value() < 0.25 {
+            self.distance.to(self.unit_system.short_unit())
         } else {
-            let mi = self.distance.to(Unit::Miles);
-            if mi.value() < 0.25 {
-                self.distance.to(Unit::Feet)
-            } else {
-                mi
-            }
+            long
         }
     }
 
     pub fn spoken(&self) -> String {
         let distance = self.converted();
-        let spoken_unit = distance.spoken_unit();
         let value = distance.value();
         let rounded = match distance.unit() {
-            Unit::Feet | Unit::Meters => {
+            Unit::Feet | Unit::Meters | Unit::Yards => {
                 if value < 1.0 {
                     round(value, 1)
                 } else if value < 10.0 {
@@ -61,13 +72,55 @@ impl SpokenDistance {
             _ => {
                 if value < 1.0 {
                     round(value, 1)
+                } else if value < MAXIMUM_HALVES {
+                    (value * 2.0).round() / 2.0
                 } else {
                     value.round()
                 }
             }
         };
-        let spoken_number = number_to_words(rounded);
-        format!("{} {}", spoken_number, spoken_unit)
+        let unit = distance.unit();
+        let gender = unit.gender(self.locale);
+        let locale = self.locale.as_str();
+
+        // Less than one unit is a fraction of the singular unit, e.g. "half a mile".
+        if let Some(fraction) = Fraction::nearest(rounded) {
+            return t!(
+                format!("distance.{}.{}", fraction.key(), gender.as_str()),
+                locale = locale,
+                unit = unit.spoken_form(1.0, self.locale)
+            )
+            .to_string();
+        }
+
+        let count = rounded.max(1.0);
+        let whole = count.trunc();
+
+        // Exactly one unit agrees with the unit's gender, e.g. "una milla" or "einem Kilometer".
+        if count == 1.0 {
+            return t!(
+                format!("distance.one.{}", gender.as_str()),
+                locale = locale,
+                unit = unit.spoken_form(count, self.locale)
+            )
+            .to_string();
+        }
+        let number = number_speller(self.locale).spell_before_noun(whole as u64, gender);
+
+        // A whole number and a half, e.g. "one and a half miles" or "un kilómetro y medio".
+        // Some languages inflect the unit for the whole value, others for the whole number.
+        if count > whole {
+            return t!(
+                format!("distance.and_a_half.{}", gender.as_str()),
+                locale = locale,
+                number = number,
+                unit = unit.spoken_form(count, self.locale),
+                whole_unit = unit.spoken_form(whole, self.locale)
+            )
+            .to_string();
+        }
+
+        format!("{} {}", number, unit.spoken_form(count, self.locale))
     }
 }
 
@@ -83,68 +136,202 @@ mod tests {
 
     #[test]
     fn test_metric_under_250m() {
-        let distance = SpokenDistance::from_meters(200.0, true);
+        let distance = SpokenDistance::from_meters(200.0, UnitSystem::Metric);
         assert_eq!(distance.spoken(), "two hundred meters");
     }
 
     #[test]
     fn test_metric_over_250m() {
-        let distance = SpokenDistance::from_meters(3000.0, true);
+        let distance = SpokenDistance::from_meters(3000.0, UnitSystem::Metric);
         assert_eq!(distance.spoken(), "three kilometers");
     }
 
     #[test]
     fn test_imperial_under_quarter_mile() {
         // 400 meters ≈ 1312 feet (under 0.25 miles)
-        let distance = SpokenDistance::from_meters(400.0, false);
+        let distance = SpokenDistance::from_meters(400.0, UnitSystem::ImperialUs);
         assert_eq!(distance.spoken(), "one thousand three hundred feet");
     }
 
     #[test]
     fn test_imperial_over_quarter_mile() {
         // 5000 meters ≈ 3.1 miles
-        let distance = SpokenDistance::from_meters(5000.0, false);
+        let distance = SpokenDistance::from_meters(5000.0, UnitSystem::ImperialUs);
+        assert_eq!(distance.spoken(), "three miles");
+    }
+
+    #[test]
+    fn test_imperial_uk_under_quarter_mile() {
+        // 400 meters ≈ 437 yards (under 0.25 miles)
+        let distance = SpokenDistance::from_meters(400.0, UnitSystem::ImperialUk);
+        assert_eq!(distance.spoken(), "four hundred fifty yards");
+    }
+
+    #[test]
+    fn test_imperial_uk_over_quarter_mile() {
+        // 5000 meters ≈ 3.1 miles
+        let distance = SpokenDistance::from_meters(5000.0, UnitSystem::ImperialUk);
         assert_eq!(distance.spoken(), "three miles");
     }
 
     #[test]
     fn test_small_metric_distance() {
         // 0.7 meters
-        let distance = SpokenDistance::from_meters(0.7, true);
-        assert_eq!(distance.spoken(), "three quarter meters");
+        let distance = SpokenDistance::from_meters(0.7, UnitSystem::Metric);
+        assert_eq!(distance.spoken(), "three quarters of a meter");
     }
 
     #[test]
     fn test_smallest_imperial_distance() {
         // 0.1 meters ≈ 0.3 feet
-        let distance = SpokenDistance::from_meters(0.1, false);
-        assert_eq!(distance.spoken(), "one quarter feet");
+        let distance = SpokenDistance::from_meters(0.1, UnitSystem::ImperialUs);
+        assert_eq!(distance.spoken(), "a quarter foot");
     }
 
     #[test]
     fn test_smaller_imperial_distance() {
         // 0.7 meters ≈ 2.3 feet
-        let distance = SpokenDistance::from_meters(0.7, false);
+        let distance = SpokenDistance::from_meters(0.7, UnitSystem::ImperialUs);
         assert_eq!(distance.spoken(), "two feet");
     }
 
     #[test]
     fn test_small_imperial_distance() {
         // 2 meters ≈ 6.6 feet
-        let distance = SpokenDistance::from_meters(2.0, false);
+        let distance = SpokenDistance::from_meters(2.0, UnitSystem::ImperialUs);
         assert_eq!(distance.spoken(), "seven feet");
     }
 
     #[test]
     fn test_fractional_kilometer() {
-        let distance = SpokenDistance::from_meters(750.0, true);
-        assert_eq!(distance.spoken(), "three quarter kilometers");
+        let distance = SpokenDistance::from_meters(750.0, UnitSystem::Metric);
+        assert_eq!(distance.spoken(), "three quarters of a kilometer");
+    }
+
+    #[test]
+    fn test_localized() {
+        let distance = SpokenDistance::from_meters(3000.0, UnitSystem::Metric);
+        assert_eq!(distance.with_locale(Locale::De).spoken(), "drei Kilometern");
+
+        let distance = SpokenDistance::from_meters(200.0, UnitSystem::Metric);
+        assert_eq!(
+            distance.with_locale(Locale::Fr).spoken(),
+            "deux cents mètres"
+        );
+
+        let distance = SpokenDistance::from_meters(5000.0, UnitSystem::ImperialUs);
+        assert_eq!(distance.with_locale(Locale::Es).spoken(), "tres millas");
     }
 
     #[test]
     fn test_fractional_mile() {
         // 1207 meters ≈ 0.75 miles
-        let distance = SpokenDistance::from_meters(1207.0, false);
-        assert_eq!(distance.spoken(), "three quarter mile");
+        let distance = SpokenDistance::from_meters(1207.0, UnitSystem::ImperialUs);
+        assert_eq!(distance.spoken(), "three quarters of a mile");
+    }
+
+    #[test]
+    fn test_half_unit() {
+        let distance = SpokenDistance::from_meters(500.0, UnitSystem::Metric);
+        assert_eq!(distance.spoken(), "half a kilometer");
+
+        // 800 meters ≈ 0.5 miles
+        let distance = SpokenDistance::from_meters(800.0, UnitSystem::ImperialUs);
+        assert_eq!(distance.with_locale(Locale::Es).spoken(), "media milla");
+
+        let distance = SpokenDistance::from_meters(500.0, UnitSystem::Metric);
+        assert_eq!(
+            distance.with_locale(Locale::Fr).spoken(),
+            "un demi-kilomètre"
+        );
+    }
+
+    #[test]
+    fn test_and_a_half() {
+        let distance = SpokenDistance::from_meters(1500.0, UnitSystem::Metric);
+        assert_eq!(distance.spoken(), "one and a half kilometers");
+
+        // 2414 meters ≈ 1.5 miles
+        let distance = SpokenDistance::from_meters(2414.0, UnitSystem::ImperialUs);
+        assert_eq!(distance.spoken(), "one and a half miles");
+        let distance = SpokenDistance::from_meters(2414.0, UnitSystem::ImperialUs);
+        assert_eq!(
+            distance.with_locale(Locale::Es).spoken(),
+            "una milla y media"
+        );
+
+        let distance = SpokenDistance::from_meters(2500.0, UnitSystem::Metric);
+        assert_eq!(
+            distance.with_locale(Locale::De).spoken(),
+            "zweieinhalb Kilometern"
+        );
+
+        let distance = SpokenDistance::from_meters(2500.0, UnitSystem::Metric);
+        assert_eq!(
+            distance.with_locale(Locale::Fr).spoken(),
+            "deux kilomètres et demi"
+        );
+
+        // Long distances are rounded to whole units
+        let distance = SpokenDistance::from_meters(12_500.0, UnitSystem::Metric);
+        assert_eq!(distance.spoken(), "thirteen kilometers");
+    }
+
+    #[test]
+    fn test_whole_units_localized() {
+        let distance = SpokenDistance::from_meters(1000.0, UnitSystem::Metric);
+        assert_eq!(distance.with_locale(Locale::Es).spoken(), "un kilómetro");
+        let distance = SpokenDistance::from_meters(1000.0, UnitSystem::Metric);
+        assert_eq!(distance.with_locale(Locale::De).spoken(), "einem Kilometer");
+        // 1609 meters is one mile, feminine in German and Spanish.
+        let distance = SpokenDistance::from_meters(1609.0, UnitSystem::ImperialUs);
+        assert_eq!(distance.with_locale(Locale::De).spoken(), "einer Meile");
+        let distance = SpokenDistance::from_meters(1609.0, UnitSystem::ImperialUs);
+        assert_eq!(distance.with_locale(Locale::Es).spoken(), "una milla");
+
+        // 33.8 kilometers ≈ 21 miles
+        let distance = SpokenDistance::from_meters(33_800.0, UnitSystem::ImperialUs);
+        assert_eq!(
+            distance.with_locale(Locale::Es).spoken(),
+            "veintiuna millas"
+        );
+    }
+
+    #[test]
+    fn test_fractions_localized() {
+        // Every unit and fraction has a phrase for the unit's gender in every locale.
+        for locale in Locale::ALL {
+            for unit in [
+                Unit::Meters,
+                Unit::Kilometers,
+                Unit::Miles,
+                Unit::Feet,
+                Unit::Yards,
+            ] {
+                for value in [0.1, 0.25, 0.5, 0.75, 1.0, 1.5, 2.5] {
+                    let spoken = SpokenDistance::from_distance(Distance::new(value, unit))
+                        .with_locale(locale)
+                        .spoken();
+                    assert!(!spoken.contains("distance."), "{}", spoken);
+                    assert!(!spoken.contains("units."), "{}", spoken);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_plural_forms() {
+        let distance = SpokenDistance::from_meters(1000.0, UnitSystem::Metric);
+        assert_eq!(distance.spoken(), "one kilometer");
+
+        // 1.1 miles is spoken as one mile, in the singular
+        let distance = SpokenDistance::from_meters(1770.0, UnitSystem::ImperialUs);
+        assert_eq!(distance.spoken(), "one mile");
+
+        let distance = SpokenDistance::from_distance(Distance::from_kilometers(1_000_000.0));
+        assert_eq!(
+            distance.with_locale(Locale::Fr).spoken(),
+            "un million de kilomètres"
+        );
     }
 }
@@ -1,71 +1,61 @@
 use rust_i18n::t;
 
-pub fn number_to_words(num: f64) -> String {
-    if num < 0.125 {
-        t!("numbers.one_eighth").to_string()
-    } else if num < 0.375 {
-        t!("numbers.one_quarter").to_string()
-    } else if num < 0.625 {
-        t!("numbers.one_half").to_string()
-    } else if num < 0.875 {
-        t!("numbers.three_quarter").to_string()
-    } else if num < 1.0 {
-        t!("numbers.1").to_string()
-    } else {
-        whole_number_to_words(num as i64)
-    }
-}
-
-fn whole_number_to_words(num: i64) -> String {
-    let mut result = String::new();
-    let mut n = num.abs();
+use crate::distance::number_speller::number_speller;
+use crate::locale::Locale;
 
-    if num < 0 {
-        result.push_str(&t!("numbers.negative"));
-        result.push(' ');
-    }
+/// A fraction of a unit, used for distances shorter than one unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fraction {
+    OneEighth,
+    OneQuarter,
+    OneHalf,
+    ThreeQuarters,
+}
 
-    if n >= 1000 {
-        let thousands = n / 1000;
-        n %= 1000;
-        result.push_str(&format!(
-            "{} {}",
-            whole_number_to_words(thousands),
-            t!("numbers.thousand")
-        ));
-        if n > 0 {
-            result.push(' ');
+impl Fraction {
+    /// The fraction nearest to `num`, or `None` when `num` is closer to one or more.
+    pub fn nearest(num: f64) -> Option<Self> {
+        if num < 0.125 {
+            Some(Fraction::OneEighth)
+        } else if num < 0.375 {
+            Some(Fraction::OneQuarter)
+        } else if num < 0.625 {
+            Some(Fraction::OneHalf)
+        } else if num < 0.875 {
+            Some(Fraction::ThreeQuarters)
+        } else {
+            None
         }
     }
 
-    if n >= 100 {
-        let hundreds = n / 100;
-        n %= 100;
-        result.push_str(&format!(
-            "{} {}",
-            whole_number_to_words(hundreds),
-            t!("numbers.hundred")
-        ));
-        if n > 0 {
-            result.push(' ');
+    /// The fraction's key in `locales/*.yml`.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Fraction::OneEighth => "one_eighth",
+            Fraction::OneQuarter => "one_quarter",
+            Fraction::OneHalf => "one_half",
+            Fraction::ThreeQuarters => "three_quarter",
         }
     }
+}
 
-    if n > 0 {
-        if n < 20 {
-            result.push_str(&t!(format!("numbers.{}", n)));
-        } else {
-            let tens = n / 10;
-            let ones = n % 10;
-            result.push_str(&t!(format!("numbers.{}", tens * 10)));
-            if ones > 0 {
-                result.push(' ');
-                result.push_str(&t!(format!("numbers.{}", ones)));
-            }
-        }
+/// Spells a distance value as words. Values below one are rounded to the nearest
+/// eighth, quarter or half; whole numbers are spelled by the locale's
+/// [`NumberSpeller`](crate::distance::number_speller::NumberSpeller).
+pub fn number_to_words(num: f64, locale: Locale) -> String {
+    let speller = number_speller(locale);
+    if num <= -1.0 {
+        return speller.spell_signed(num as i64);
     }
 
-    result.trim().to_string()
+    match Fraction::nearest(num) {
+        Some(fraction) => t!(
+            format!("numbers.{}", fraction.key()),
+            locale = locale.as_str()
+        )
+        .to_string(),
+        None => speller.spell_signed(num.max(1.0) as i64),
+    }
 }
 
 #[cfg(test)]
@@ -89,7 +79,7 @@ mod tests {
 
         let mut snapshot = String::new();
         for num in test_cases {
-            let words = number_to_words(num);
+            let words = number_to_words(num, Locale::En);
             snapshot.push_str(&format!("{}: {}\n", num, words));
         }
 
@@ -100,19 +90,54 @@ mod tests {
     fn test_edge_cases() {
         let test_cases = vec![
             f64::MIN_POSITIVE, // Smallest positive float
-            f64::MAX, // FIXME: This output is nonsensical without adding millions, billions, etc.
-            -0.0,     // Negative zero
-            0.1,      // Small decimal
-            0.49,     // Just under 0.5
-            0.51,     // Just over 0.5
+            f64::MAX,          // Saturates to the largest whole number
+            -0.0,              // Negative zero
+            0.1,               // Small decimal
+            0.49,              // Just under 0.5
+            0.51,              // Just over 0.5
         ];
 
         let mut snapshot = String::new();
         for num in test_cases {
-            let words = number_to_words(num);
+            let words = number_to_words(num, Locale::En);
             snapshot.push_str(&format!("{}: {}\n", num, words));
         }
 
         assert_snapshot!(snapshot);
     }
+
+    #[test]
+    fn test_number_conversions_localized() {
+        let test_cases = vec![
+            0.5,
+            1.0,
+            7.0,
+            16.0,
+            21.0,
+            71.0,
+            80.0,
+            91.0,
+            100.0,
+            101.0,
+            200.0,
+            250.0,
+            500.0,
+            1000.0,
+            1001.0,
+            21000.0,
+            1_000_000.0,
+        ];
+
+        for locale in [Locale::De, Locale::Fr, Locale::Es] {
+            let mut snapshot = String::new();
+            for num in &test_cases {
+                let words = number_to_words(*num, locale);
+                snapshot.push_str(&format!("{}: {}\n", num, words));
+            }
+
+            insta::with_settings!({ snapshot_suffix => locale.as_str() }, {
+                assert_snapshot!(snapshot);
+            });
+        }
+    }
 }
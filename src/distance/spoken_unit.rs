@@ -1,57 +1,86 @@
 use rust_i18n::t;
 
+use crate::distance::plural::PluralCategory;
+use crate::locale::Locale;
+
+/// The grammatical gender of a unit's name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gender {
+    Masculine,
+    Feminine,
+    Neuter,
+}
+
+impl Gender {
+    /// The key suffix used for the gender in `locales/*.yml`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Gender::Masculine => "masculine",
+            Gender::Feminine => "feminine",
+            Gender::Neuter => "neuter",
+        }
+    }
+}
+
 pub trait SpokenUnit {
-    fn get_form(count: f64) -> String;
+    /// The unit's key in `locales/locales.yml`, e.g. "mile" for `units.mile.one`.
+    const KEY: &'static str;
+
+    /// The unit's form following `count`, chosen by the locale's plural category for it.
+    fn get_form(count: f64, locale: Locale) -> String {
+        let category = PluralCategory::for_count(count, locale);
+        t!(
+            format!("units.{}.{}", Self::KEY, category.as_str()),
+            locale = locale.as_str()
+        )
+        .to_string()
+    }
+
+    /// The unit's grammatical gender, which selects the fraction phrase ("medio metro", "media milla").
+    fn gender(locale: Locale) -> Gender {
+        let gender = t!(
+            format!("units.{}.gender", Self::KEY),
+            locale = locale.as_str()
+        );
+        match gender.as_ref() {
+            "masculine" => Gender::Masculine,
+            "feminine" => Gender::Feminine,
+            _ => Gender::Neuter,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct SpokenMile;
 
 impl SpokenUnit for SpokenMile {
-    fn get_form(count: f64) -> String {
-        if count <= 1.0 {
-            t!("units.singular.mile").to_string()
-        } else {
-            t!("units.plural.miles").to_string()
-        }
-    }
+    const KEY: &'static str = "mile";
 }
 
 #[derive(Debug, Clone)]
 pub struct SpokenFoot;
 
 impl SpokenUnit for SpokenFoot {
-    fn get_form(count: f64) -> String {
-        if count == 1.0 {
-            t!("units.singular.foot").to_string()
-        } else {
-            t!("units.plural.feet").to_string()
-        }
-    }
+    const KEY: &'static str = "foot";
+}
+
+#[derive(Debug, Clone)]
+pub struct SpokenYard;
+
+impl SpokenUnit for SpokenYard {
+    const KEY: &'static str = "yard";
 }
 
 #[derive(Debug, Clone)]
 pub struct SpokenKilometer;
 
 impl SpokenUnit for SpokenKilometer {
-    fn get_form(count: f64) -> String {
-        if count == 1.0 {
-            t!("units.singular.kilometer").to_string()
-        } else {
-            t!("units.plural.kilometers").to_string()
-        }
-    }
+    const KEY: &'static str = "kilometer";
 }
 
 #[derive(Debug, Clone)]
 pub struct SpokenMeter;
 
 impl SpokenUnit for SpokenMeter {
-    fn get_form(count: f64) -> String {
-        if count == 1.0 {
-            t!("units.singular.meter").to_string()
-        } else {
-            t!("units.plural.meters").to_string()
-        }
-    }
+    const KEY: &'static str = "meter";
 }
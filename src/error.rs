@@ -0,0 +1,172 @@
+use std::fmt;
+
+/// Why a route could not be extended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The route has no legs.
+    MissingLegs,
+
+    /// The JSON doesn't match the OSRM route response model.
+    InvalidJson(String),
+
+    /// A step has no geometry.
+    MissingGeometry,
+
+    /// A step has no maneuver.
+    MissingManeuver,
+
+    /// A step's geometry is not an encoded polyline of the expected precision.
+    InvalidPolyline(String),
+
+    /// The step polylines match their maneuver locations and distances at no known precision.
+    UnknownPolylinePrecision,
+
+    /// A field holds a GeoJSON object other than a LineString of coordinates, or GeoJSON where
+    /// only an encoded polyline is read.
+    UnsupportedGeoJson { field: &'static str },
+
+    /// A field holds a JSON value of a type which is not supported, e.g. an array.
+    UnsupportedValue {
+        field: &'static str,
+        kind: &'static str,
+    },
+
+    /// A leg's annotation doesn't cover one entry per segment of its steps' geometry.
+    AnnotationLengthMismatch {
+        leg_index: usize,
+        expected: usize,
+        actual: usize,
+    },
+
+    /// A step's segments run past the end of its leg's `field` annotation, which has `len`
+    /// entries.
+    AnnotationOutOfBounds {
+        field: &'static str,
+        start: usize,
+        end: usize,
+        len: usize,
+    },
+
+    /// The legs read with the fields the OpenAPI model drops are not the route's legs.
+    LegCountMismatch { expected: usize, actual: usize },
+
+    /// A leg read with the fields the OpenAPI model drops doesn't have the route leg's steps.
+    StepCountMismatch {
+        leg_index: usize,
+        expected: usize,
+        actual: usize,
+    },
+
+    /// An intersection's `geometry_index` is not the step coordinate at its location. The step
+    /// covers the leg's coordinates `first` through `last`.
+    IntersectionIndexMismatch {
+        intersection_index: usize,
+        geometry_index: usize,
+        first: usize,
+        last: usize,
+    },
+
+    /// An error in a specific step of the route.
+    Step {
+        leg_index: usize,
+        step_index: usize,
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Attributes the error to the step at `step_index` of the leg at `leg_index`.
+    pub(crate) fn in_step(self, leg_index: usize, step_index: usize) -> Self {
+        Error::Step {
+            leg_index,
+            step_index,
+            source: Box::new(self),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingLegs => write!(f, "route has no legs"),
+            Error::InvalidJson(message) => write!(f, "invalid route JSON: {}", message),
+            Error::MissingGeometry => write!(f, "step has no geometry"),
+            Error::MissingManeuver => write!(f, "step has no maneuver"),
+            Error::InvalidPolyline(message) => write!(f, "invalid polyline: {}", message),
+            Error::UnknownPolylinePrecision => write!(
+                f,
+                "step polylines match their maneuvers at neither precision 5 nor 6"
+            ),
+            Error::UnsupportedGeoJson { field } => write!(
+                f,
+                "{} field contains GeoJSON which is not supported; only LineString geometries are read",
+                field
+            ),
+            Error::UnsupportedValue { field, kind } => {
+                write!(
+                    f,
+                    "{} field contains a JSON {}, which is not supported",
+                    field, kind
+                )
+            }
+            Error::AnnotationLengthMismatch {
+                leg_index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "leg {} annotation has {} entries, but its geometry has {} segments",
+                leg_index, actual, expected
+            ),
+            Error::AnnotationOutOfBounds {
+                field,
+                start,
+                end,
+                len,
+            } => write!(
+                f,
+                "step covers segments {} to {}, but the {} annotation has {} entries",
+                start, end, field, len
+            ),
+            Error::LegCountMismatch { expected, actual } => write!(
+                f,
+                "route has {} legs, but {} were read with their intersections",
+                expected, actual
+            ),
+            Error::StepCountMismatch {
+                leg_index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "leg {} has {} steps, but {} were read with their intersections",
+                leg_index, expected, actual
+            ),
+            Error::IntersectionIndexMismatch {
+                intersection_index,
+                geometry_index,
+                first,
+                last,
+            } => write!(
+                f,
+                "intersection {} has geometry index {}, but its location is not the step's \
+                 coordinate there (coordinates {} to {})",
+                intersection_index, geometry_index, first, last
+            ),
+            Error::Step {
+                leg_index,
+                step_index,
+                source,
+            } => write!(f, "leg {} step {}: {}", leg_index, step_index, source),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Step { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
@@ -1 +1,3 @@
+pub mod route_geometry;
+pub mod step_geometry;
 pub mod utilities;
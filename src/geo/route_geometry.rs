@@ -0,0 +1,140 @@
+use geo::Coord;
+use osrm_openapi_models::models::Route;
+
+use crate::Error;
+
+use super::step_geometry::{GeometryProjection, StepGeometry};
+
+/// The point of a route nearest to a location.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RouteProjection {
+    pub leg_index: usize,
+    pub step_index: usize,
+    /// The nearest point on the step's geometry.
+    pub step: GeometryProjection,
+    /// Meters from the start of the route to the nearest point.
+    pub distance_along_route: f64,
+}
+
+/// The geometry of every step of a route, for finding how far along it a location is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteGeometry {
+    steps: Vec<RouteGeometryStep>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RouteGeometryStep {
+    leg_index: usize,
+    step_index: usize,
+    /// Meters from the start of the route to the start of the step.
+    start: f64,
+    geometry: StepGeometry,
+}
+
+impl RouteGeometry {
+    pub fn from_route(route: &Route, polyline_precision: u32) -> Result<Self, Error> {
+        let mut start = 0.0;
+        let mut steps = Vec::new();
+
+        let legs = route.legs.as_ref().ok_or(Error::MissingLegs)?;
+        for (leg_index, leg) in legs.iter().enumerate() {
+            for (step_index, step) in leg.steps.iter().flatten().enumerate() {
+                let geometry = StepGeometry::from_step(step, polyline_precision)
+                    .and_then(|geometry| geometry.ok_or(Error::MissingGeometry))
+                    .map_err(|error| error.in_step(leg_index, step_index))?;
+                let length = geometry.length();
+
+                steps.push(RouteGeometryStep {
+                    leg_index,
+                    step_index,
+                    start,
+                    geometry,
+                });
+                start += length;
+            }
+        }
+
+        Ok(RouteGeometry { steps })
+    }
+
+    /// Length of the route's geometry in meters.
+    pub fn length(&self) -> f64 {
+        self.steps
+            .last()
+            .map_or(0.0, |step| step.start + step.geometry.length())
+    }
+
+    /// The point of the route nearest to `location`. Where the route passes as near more than
+    /// once, the earliest point is taken.
+    pub fn project(&self, location: Coord) -> Option<RouteProjection> {
+        self.steps
+            .iter()
+            .filter_map(|step| {
+                step.geometry
+                    .project(location)
+                    .map(|projection| RouteProjection {
+                        leg_index: step.leg_index,
+                        step_index: step.step_index,
+                        step: projection,
+                        distance_along_route: step.start + projection.distance_along,
+                    })
+            })
+            .min_by(|a, b| a.step.offset.total_cmp(&b.step.offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{POLYLINE_PRECISION, testing::load_route};
+
+    #[test]
+    fn test_project_onto_route() {
+        let route = load_route("./fixtures/valhalla-legs.json", 0);
+        let geometry = RouteGeometry::from_route(&route, POLYLINE_PRECISION).unwrap();
+
+        // The geometry is about as long as the route.
+        let distance = route.distance.unwrap();
+        assert!((geometry.length() - distance).abs() < distance * 0.01);
+
+        // A location part way along a step in the second leg.
+        let step = StepGeometry::from_step(
+            &route.legs.as_ref().unwrap()[1].steps.as_ref().unwrap()[1],
+            POLYLINE_PRECISION,
+        )
+        .unwrap()
+        .unwrap();
+        let location = step.position_at_distance(step.length() / 2.0).unwrap();
+
+        let projection = geometry.project(location.coordinate).unwrap();
+        assert_eq!((projection.leg_index, projection.step_index), (1, 1));
+        assert!(projection.step.offset < 0.01);
+        assert!((projection.step.distance_along - step.length() / 2.0).abs() < 0.01);
+
+        let first_leg = geometry
+            .steps
+            .iter()
+            .filter(|step| step.leg_index == 0)
+            .map(|step| step.geometry.length())
+            .sum::<f64>();
+        let second_leg_first_step = geometry.steps.iter().find(|step| step.leg_index == 1);
+        let expected =
+            first_leg + second_leg_first_step.unwrap().geometry.length() + step.length() / 2.0;
+        assert!((projection.distance_along_route - expected).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_route_geometry_errors() {
+        assert_eq!(
+            RouteGeometry::from_route(&Route::default(), POLYLINE_PRECISION),
+            Err(Error::MissingLegs)
+        );
+
+        let mut route = load_route("./fixtures/valhalla-short.json", 0);
+        route.legs.as_mut().unwrap()[0].steps.as_mut().unwrap()[3].geometry = None;
+        assert_eq!(
+            RouteGeometry::from_route(&route, POLYLINE_PRECISION),
+            Err(Error::MissingGeometry.in_step(0, 3))
+        );
+    }
+}
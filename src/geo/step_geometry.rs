@@ -0,0 +1,265 @@
+use geo::{Bearing, Coord, Distance, Haversine, Point};
+use osrm_openapi_models::models::RouteStep;
+
+use crate::{Error, osrm::RouteStepExt};
+
+/// A position along a step's geometry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeometryPosition {
+    /// Index of the segment the position is on, from coordinate `segment_index` to the next.
+    pub segment_index: usize,
+    /// How far along the segment the position is, from 0 at its start to 1 at its end.
+    pub fraction: f64,
+    /// The position, interpolated between the segment's coordinates.
+    pub coordinate: Coord,
+}
+
+/// The point of a geometry nearest to a location.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeometryProjection {
+    /// Where on the geometry the nearest point is.
+    pub position: GeometryPosition,
+    /// Meters from the start of the geometry to the nearest point.
+    pub distance_along: f64,
+    /// Meters from the location to the nearest point, i.e. how far off the geometry it is.
+    pub offset: f64,
+    /// Bearing of the nearest point's segment in degrees, clockwise from north.
+    pub bearing: f64,
+}
+
+/// A step's coordinates with the distance to each of them, measured once so positions along the
+/// step can be looked up without decoding or measuring it again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepGeometry {
+    coordinates: Vec<Coord>,
+    /// Meters from the first coordinate to each coordinate.
+    distances: Vec<f64>,
+}
+
+impl StepGeometry {
+    pub fn new(coordinates: Vec<Coord>) -> Self {
+        let mut distance = 0.0;
+        let distances = coordinates
+            .iter()
+            .enumerate()
+            .map(|(index, coord)| {
+                if index > 0 {
+                    distance += Haversine
+                        .distance(Point::from(coordinates[index - 1]), Point::from(*coord));
+                }
+                distance
+            })
+            .collect();
+
+        StepGeometry {
+            coordinates,
+            distances,
+        }
+    }
+
+    /// Decodes a step's geometry, or `None` when it has none.
+    pub fn from_step(step: &RouteStep, polyline_precision: u32) -> Result<Option<Self>, Error> {
+        Ok(step
+            .geometry_coordinates(polyline_precision)?
+            .map(StepGeometry::new))
+    }
+
+    pub fn coordinates(&self) -> &[Coord] {
+        &self.coordinates
+    }
+
+    /// Meters from the first coordinate to each coordinate.
+    pub fn distances(&self) -> &[f64] {
+        &self.distances
+    }
+
+    /// Length of the geometry in meters.
+    pub fn length(&self) -> f64 {
+        self.distances.last().copied().unwrap_or(0.0)
+    }
+
+    /// The position `distance` meters along the geometry, clamped to its start and end. `None`
+    /// when the geometry has no segments.
+    pub fn position_at_distance(&self, distance: f64) -> Option<GeometryPosition> {
+        let last_segment = self.coordinates.len().checked_sub(2)?;
+        let distance = distance.clamp(0.0, self.length());
+
+        let segment_index = self
+            .distances
+            .partition_point(|start| *start <= distance)
+            .saturating_sub(1)
+            .min(last_segment);
+
+        let start = self.coordinates[segment_index];
+        let end = self.coordinates[segment_index + 1];
+        let segment_length = self.distances[segment_index + 1] - self.distances[segment_index];
+        let fraction = if segment_length > 0.0 {
+            ((distance - self.distances[segment_index]) / segment_length).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        Some(GeometryPosition {
+            segment_index,
+            fraction,
+            coordinate: Coord {
+                x: start.x + (end.x - start.x) * fraction,
+                y: start.y + (end.y - start.y) * fraction,
+            },
+        })
+    }
+
+    /// The point of the geometry nearest to `location`, the inverse of
+    /// [`StepGeometry::position_at_distance`]. `None` when the geometry has no segments.
+    pub fn project(&self, location: Coord) -> Option<GeometryProjection> {
+        (0..self.coordinates.len().saturating_sub(1))
+            .map(|segment_index| self.project_on_segment(segment_index, location))
+            .min_by(|a, b| a.offset.total_cmp(&b.offset))
+    }
+
+    fn project_on_segment(&self, segment_index: usize, location: Coord) -> GeometryProjection {
+        let start = self.coordinates[segment_index];
+        let end = self.coordinates[segment_index + 1];
+
+        // Segments are short enough to project onto in a plane, with longitude scaled to the
+        // segment's latitude.
+        let scale = start.y.to_radians().cos();
+        let (dx, dy) = ((end.x - start.x) * scale, end.y - start.y);
+        let (px, py) = ((location.x - start.x) * scale, location.y - start.y);
+        let length_squared = dx * dx + dy * dy;
+        let fraction = if length_squared > 0.0 {
+            ((px * dx + py * dy) / length_squared).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        let coordinate = Coord {
+            x: start.x + (end.x - start.x) * fraction,
+            y: start.y + (end.y - start.y) * fraction,
+        };
+        let segment_length = self.distances[segment_index + 1] - self.distances[segment_index];
+
+        GeometryProjection {
+            position: GeometryPosition {
+                segment_index,
+                fraction,
+                coordinate,
+            },
+            distance_along: self.distances[segment_index] + segment_length * fraction,
+            offset: Haversine.distance(Point::from(location), Point::from(coordinate)),
+            bearing: Haversine.bearing(Point::from(start), Point::from(end)),
+        }
+    }
+
+    /// The index of the coordinate nearest to `distance` meters along the geometry.
+    pub fn coordinate_index_at_distance(&self, distance: f64) -> Option<usize> {
+        self.position_at_distance(distance).map(|position| {
+            if position.fraction < 0.5 {
+                position.segment_index
+            } else {
+                position.segment_index + 1
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::utilities::get_coordinates_from_geometry;
+
+    fn geometry() -> StepGeometry {
+        // Three points, about 51 m and 45 m apart.
+        StepGeometry::new(get_coordinates_from_geometry("epxkF|`miVfAfA~@~@", 5).unwrap())
+    }
+
+    #[test]
+    fn test_distances() {
+        let geometry = geometry();
+        assert_eq!(geometry.distances().len(), 3);
+        assert_eq!(geometry.distances()[0], 0.0);
+        assert!((geometry.distances()[1] - 50.752).abs() < 0.001);
+        assert!((geometry.length() - 95.865).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_position_at_distance() {
+        let geometry = geometry();
+        let coordinates = geometry.coordinates();
+
+        let start = geometry.position_at_distance(0.0).unwrap();
+        assert_eq!((start.segment_index, start.fraction), (0, 0.0));
+        assert_eq!(start.coordinate, coordinates[0]);
+
+        let halfway = geometry
+            .position_at_distance(geometry.distances()[1] / 2.0)
+            .unwrap();
+        assert_eq!(halfway.segment_index, 0);
+        assert!((halfway.fraction - 0.5).abs() < 1e-9);
+        assert!((halfway.coordinate.x - (coordinates[0].x + coordinates[1].x) / 2.0).abs() < 1e-9);
+        assert!((halfway.coordinate.y - (coordinates[0].y + coordinates[1].y) / 2.0).abs() < 1e-9);
+
+        let second = geometry.position_at_distance(60.0).unwrap();
+        assert_eq!(second.segment_index, 1);
+        assert!(second.fraction > 0.0 && second.fraction < 0.5);
+
+        // Distances past either end are clamped to it.
+        let end = geometry.position_at_distance(1000.0).unwrap();
+        assert_eq!((end.segment_index, end.fraction), (1, 1.0));
+        assert_eq!(end.coordinate, coordinates[2]);
+        assert_eq!(geometry.position_at_distance(-10.0), Some(start));
+    }
+
+    #[test]
+    fn test_position_without_segments() {
+        let point = StepGeometry::new(vec![Coord { x: 1.0, y: 2.0 }]);
+        assert_eq!(point.length(), 0.0);
+        assert_eq!(point.position_at_distance(0.0), None);
+        assert_eq!(
+            StepGeometry::new(Vec::new()).position_at_distance(0.0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_project() {
+        let geometry = geometry();
+
+        // A point on the geometry projects onto itself.
+        let on = geometry.position_at_distance(60.0).unwrap();
+        let projection = geometry.project(on.coordinate).unwrap();
+        assert_eq!(projection.position.segment_index, 1);
+        assert!((projection.distance_along - 60.0).abs() < 0.01);
+        assert!(projection.offset < 0.01);
+
+        // The segment runs southwest, so this point is off to its southeast side.
+        let off = Coord {
+            x: on.coordinate.x + 0.00032,
+            y: on.coordinate.y - 0.000194,
+        };
+        let projection = geometry.project(off).unwrap();
+        assert_eq!(projection.position.segment_index, 1);
+        assert!((projection.distance_along - 60.0).abs() < 1.0);
+        assert!((projection.offset - 35.1).abs() < 0.5);
+        assert!((projection.bearing - 217.9).abs() < 0.1);
+
+        // Points beyond the ends project onto them.
+        let before = Coord {
+            x: geometry.coordinates()[0].x + 0.001,
+            y: geometry.coordinates()[0].y + 0.001,
+        };
+        let projection = geometry.project(before).unwrap();
+        assert_eq!(projection.distance_along, 0.0);
+        assert_eq!(projection.position.coordinate, geometry.coordinates()[0]);
+
+        assert_eq!(StepGeometry::new(Vec::new()).project(before), None);
+    }
+
+    #[test]
+    fn test_coordinate_index_at_distance() {
+        let geometry = geometry();
+        assert_eq!(geometry.coordinate_index_at_distance(25.0), Some(0));
+        assert_eq!(geometry.coordinate_index_at_distance(25.4), Some(1));
+        assert_eq!(geometry.coordinate_index_at_distance(74.0), Some(2));
+    }
+}
@@ -1,15 +1,43 @@
-use geo::{Coord, Distance, Haversine};
+use geo::Coord;
 use polyline::decode_polyline;
+use serde_json::Value;
+
+use crate::Error;
+
+use super::step_geometry::StepGeometry;
 
 pub fn get_coordinates_from_geometry(
     geometry: &str,
     polyline_precision: u32,
-) -> Option<Vec<Coord>> {
+) -> Result<Vec<Coord>, Error> {
     decode_polyline(geometry, polyline_precision)
-        .ok()?
-        .into_iter()
-        .collect::<Vec<_>>()
-        .into()
+        .map(|line_string| line_string.into_iter().collect())
+        .map_err(|error| Error::InvalidPolyline(error.to_string()))
+}
+
+/// Reads the coordinates of a GeoJSON LineString, e.g. a step geometry requested with
+/// `geometries=geojson`.
+pub fn get_coordinates_from_geojson(geometry: &Value) -> Result<Vec<Coord>, Error> {
+    let unsupported = Error::UnsupportedGeoJson { field: "geometry" };
+
+    if geometry.get("type").and_then(Value::as_str) != Some("LineString") {
+        return Err(unsupported);
+    }
+
+    geometry
+        .get("coordinates")
+        .and_then(Value::as_array)
+        .ok_or(unsupported.clone())?
+        .iter()
+        .map(|position| match position.as_array().map(Vec::as_slice) {
+            Some([x, y, ..]) => Some(Coord {
+                x: x.as_f64()?,
+                y: y.as_f64()?,
+            }),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or(unsupported)
 }
 
 pub fn get_coordinate_index(
@@ -17,49 +45,13 @@ pub fn get_coordinate_index(
     polyline_precision: u32,
     distance: f64,
 ) -> Option<usize> {
-    let line_string = match decode_polyline(geometry, polyline_precision) {
-        Ok(coords) => coords,
-        Err(_) => return None,
-    };
-
-    let points: Vec<_> = line_string.points().collect();
-
-    if points.len() < 2 {
-        return None;
-    }
-
-    // Return first point for distance 0
-    if distance == 0.0 {
-        return Some(0);
-    }
-
-    let mut accumulated_distance = 0.0;
-
-    // Iterate through points calculating cumulative distance
-    for i in 1..points.len() {
-        let current_point = &points[i];
-        let prev_point = &points[i - 1];
-        let segment_distance = Haversine.distance(*prev_point, *current_point);
-
-        let next_accumulated = accumulated_distance + segment_distance;
-
-        // Find midpoint between accumulated and next_accumulated
-        let segment_midpoint = accumulated_distance + (segment_distance / 2.0);
-
-        // If target distance is closer to previous point
-        if distance < segment_midpoint {
-            return Some(i - 1);
-        }
-        // If target distance is closer to current point
-        else if distance <= next_accumulated {
-            return Some(i);
-        }
-
-        accumulated_distance = next_accumulated;
-    }
+    let coordinates = get_coordinates_from_geometry(geometry, polyline_precision).ok()?;
+    get_coordinate_index_at_distance(&coordinates, distance)
+}
 
-    // Return Last if distance is beyond total length
-    Some(points.len() - 1)
+/// The index of the coordinate nearest to `distance` meters along `coordinates`.
+pub fn get_coordinate_index_at_distance(coordinates: &[Coord], distance: f64) -> Option<usize> {
+    StepGeometry::new(coordinates.to_vec()).coordinate_index_at_distance(distance)
 }
 
 #[cfg(test)]
@@ -75,6 +67,32 @@ mod tests {
         assert_snapshot!(format!("{:?}", coords));
     }
 
+    #[test]
+    fn test_coordinates_from_invalid_geometry() {
+        assert!(matches!(
+            get_coordinates_from_geometry("invalid", 5),
+            Err(Error::InvalidPolyline(_))
+        ));
+    }
+
+    #[test]
+    fn test_coordinates_from_geojson() {
+        let geometry = serde_json::json!({
+            "type": "LineString",
+            "coordinates": [[-122.29663, 38.79699], [-122.29699, 38.79663], [-122.29731, 38.79631]]
+        });
+        assert_eq!(
+            get_coordinates_from_geojson(&geometry),
+            get_coordinates_from_geometry("epxkF|`miVfAfA~@~@", 5)
+        );
+
+        let point = serde_json::json!({"type": "Point", "coordinates": [-122.29663, 38.79699]});
+        assert_eq!(
+            get_coordinates_from_geojson(&point),
+            Err(Error::UnsupportedGeoJson { field: "geometry" })
+        );
+    }
+
     #[test]
     fn test_get_coordinate_index() {
         // A simple straight line with 3 points, each ~50m apart
@@ -0,0 +1,264 @@
+use std::collections::HashSet;
+
+use osrm_openapi_models::models::{Route, Waypoint};
+use rust_i18n::t;
+
+use crate::{distance::spoken_numbers::number_to_words, locale::Locale};
+
+/// Highest stop number with a dedicated ordinal word ("first" .. "tenth").
+const MAX_ORDINAL: usize = 10;
+
+/// Describes where a leg ends. Every leg but the last ends at an intermediate stop,
+/// the last one ends at the final destination.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Arrival {
+    /// An intermediate stop that splits the route into legs, numbered from 1.
+    Stop {
+        ordinal: usize,
+        name: Option<String>,
+    },
+
+    /// The final destination of the route.
+    Destination { name: Option<String> },
+}
+
+impl Arrival {
+    /// Determines the arrival for each leg of a route.
+    ///
+    /// `waypoints` are the `RouteResponse.waypoints`. These include the pass-through
+    /// waypoints listed by each leg's `via_waypoints`, which do not end a leg and are
+    /// skipped when matching waypoints to legs.
+    pub fn for_legs(route: &Route, waypoints: &[Waypoint]) -> Vec<Arrival> {
+        let legs = route.legs.as_deref().unwrap_or_default();
+
+        let via_indices = legs
+            .iter()
+            .flat_map(|leg| leg.via_waypoints.iter().flatten())
+            .filter_map(|via| via.waypoint_index)
+            .filter_map(|index| usize::try_from(index).ok())
+            .collect::<HashSet<_>>();
+
+        let stop_names = waypoints
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !via_indices.contains(index))
+            .map(|(_, waypoint)| waypoint.name.clone().filter(|name| !name.is_empty()))
+            .collect::<Vec<_>>();
+
+        (0..legs.len())
+            .map(|leg_index| {
+                // Leg n ends at the waypoint after its start, the first waypoint is the origin.
+                let name = stop_names.get(leg_index + 1).cloned().flatten();
+                if leg_index + 1 == legs.len() {
+                    Arrival::Destination { name }
+                } else {
+                    Arrival::Stop {
+                        ordinal: leg_index + 1,
+                        name,
+                    }
+                }
+            })
+            .collect()
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Arrival::Stop { name, .. } | Arrival::Destination { name } => name.as_deref(),
+        }
+    }
+
+    /// The announcement made on arrival, e.g. "You have arrived at your first stop, Main Street."
+    ///
+    /// Returns `None` for an unnamed final destination so the routing server's own
+    /// arrival instruction is kept.
+    pub fn arrived(&self, locale: Locale) -> Option<String> {
+        match self {
+            Arrival::Stop { ordinal, name } => Some(match name {
+                Some(name) => t!(
+                    "You have arrived at your %{stop}, %{name}.",
+                    locale = locale.as_str(),
+                    stop = stop_phrase(*ordinal, locale),
+                    name = name
+                )
+                .to_string(),
+                None => t!(
+                    "You have arrived at your %{stop}.",
+                    locale = locale.as_str(),
+                    stop = stop_phrase(*ordinal, locale)
+                )
+                .to_string(),
+            }),
+            Arrival::Destination { name } => name.as_ref().map(|name| {
+                t!(
+                    "You have arrived at your destination, %{name}.",
+                    locale = locale.as_str(),
+                    name = name
+                )
+                .to_string()
+            }),
+        }
+    }
+
+    /// The announcement made ahead of the arrival, e.g. "You will arrive at your first stop, Main Street."
+    pub fn arriving(&self, locale: Locale) -> Option<String> {
+        match self {
+            Arrival::Stop { ordinal, name } => Some(match name {
+                Some(name) => t!(
+                    "You will arrive at your %{stop}, %{name}.",
+                    locale = locale.as_str(),
+                    stop = stop_phrase(*ordinal, locale),
+                    name = name
+                )
+                .to_string(),
+                None => t!(
+                    "You will arrive at your %{stop}.",
+                    locale = locale.as_str(),
+                    stop = stop_phrase(*ordinal, locale)
+                )
+                .to_string(),
+            }),
+            Arrival::Destination { name } => name.as_ref().map(|name| {
+                t!(
+                    "You will arrive at your destination, %{name}.",
+                    locale = locale.as_str(),
+                    name = name
+                )
+                .to_string()
+            }),
+        }
+    }
+}
+
+/// "first stop", "second stop", ... falling back to "stop number eleven" past the known ordinals.
+fn stop_phrase(ordinal: usize, locale: Locale) -> String {
+    if (1..=MAX_ORDINAL).contains(&ordinal) {
+        t!(
+            "%{ordinal} stop",
+            locale = locale.as_str(),
+            ordinal = t!(format!("ordinals.{}", ordinal), locale = locale.as_str())
+        )
+        .to_string()
+    } else {
+        t!(
+            "stop number %{number}",
+            locale = locale.as_str(),
+            number = number_to_words(ordinal as f64, locale)
+        )
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fixtures::load_route_response, load_route};
+
+    #[test]
+    fn test_for_legs_multi_leg() {
+        let response = load_route_response("./fixtures/valhalla-legs.json");
+        let route = load_route("./fixtures/valhalla-legs.json", 0);
+        let arrivals = Arrival::for_legs(&route, &response.waypoints.unwrap());
+
+        assert_eq!(
+            arrivals,
+            vec![
+                Arrival::Stop {
+                    ordinal: 1,
+                    name: Some("Elberta Avenue".to_string())
+                },
+                Arrival::Destination {
+                    name: Some("Patterson Road".to_string())
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_for_legs_skips_via_waypoints() {
+        // The vias fixture has four waypoints, two of which are pass-through vias on its only leg.
+        let response = load_route_response("./fixtures/valhalla-vias.json");
+        let route = load_route("./fixtures/valhalla-vias.json", 0);
+        let arrivals = Arrival::for_legs(&route, &response.waypoints.unwrap());
+
+        assert_eq!(
+            arrivals,
+            vec![Arrival::Destination {
+                name: Some("200 West".to_string())
+            }]
+        );
+    }
+
+    #[test]
+    fn test_for_legs_without_waypoints() {
+        let route = load_route("./fixtures/valhalla-legs.json", 0);
+        let arrivals = Arrival::for_legs(&route, &[]);
+
+        assert_eq!(
+            arrivals,
+            vec![
+                Arrival::Stop {
+                    ordinal: 1,
+                    name: None
+                },
+                Arrival::Destination { name: None },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arrived() {
+        let stop = Arrival::Stop {
+            ordinal: 2,
+            name: Some("Elberta Avenue".to_string()),
+        };
+        assert_eq!(
+            stop.arrived(Locale::En).unwrap(),
+            "You have arrived at your second stop, Elberta Avenue."
+        );
+        assert_eq!(
+            stop.arriving(Locale::En).unwrap(),
+            "You will arrive at your second stop, Elberta Avenue."
+        );
+
+        let unnamed = Arrival::Stop {
+            ordinal: 12,
+            name: None,
+        };
+        assert_eq!(
+            unnamed.arrived(Locale::En).unwrap(),
+            "You have arrived at your stop number twelve."
+        );
+
+        let destination = Arrival::Destination {
+            name: Some("Patterson Road".to_string()),
+        };
+        assert_eq!(
+            destination.arrived(Locale::En).unwrap(),
+            "You have arrived at your destination, Patterson Road."
+        );
+        assert_eq!(
+            Arrival::Destination { name: None }.arrived(Locale::En),
+            None
+        );
+    }
+
+    #[test]
+    fn test_arrived_localized() {
+        let stop = Arrival::Stop {
+            ordinal: 1,
+            name: Some("Elberta Avenue".to_string()),
+        };
+        assert_eq!(
+            stop.arrived(Locale::De).unwrap(),
+            "Sie haben Ihren ersten Halt erreicht, Elberta Avenue."
+        );
+        assert_eq!(
+            stop.arriving(Locale::Fr).unwrap(),
+            "Vous arriverez à votre premier arrêt, Elberta Avenue."
+        );
+        assert_eq!(
+            stop.arrived(Locale::Es).unwrap(),
+            "Ha llegado a su primera parada, Elberta Avenue."
+        );
+    }
+}
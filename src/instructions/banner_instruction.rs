@@ -1,25 +1,66 @@
-use crate::instructions::utilities::step_maneuver_name;
-use osrm_openapi_models::models::{
-    BannerContent, BannerContentComponentsInner, BannerInstruction, ManeuverType, RouteStep,
+use crate::{
+    instructions::{
+        arrival::Arrival,
+        lanes::lane_components,
+        utilities::{banner_components_text, step_banner_components, step_other_destinations},
+    },
+    locale::Locale,
+    osrm::models::{ExtendedBannerComponent, ExtendedBannerContent, ExtendedBannerInstruction},
 };
+use osrm_openapi_models::models::{BannerInstruction, ManeuverModifier, ManeuverType, RouteStep};
 use rust_i18n::t;
 
+/// A next step shorter than this (in meters) gets a sub banner previewing the maneuver after it.
+const SUB_BANNER_MAXIMUM_DISTANCE: f64 = 150.0;
+
 #[derive(Debug)]
 pub struct BannerInstructionsFactory {
     next_step: Option<RouteStep>,
     step: Option<RouteStep>,
+    step_after_next: Option<RouteStep>,
+    arrival: Option<Arrival>,
+    locale: Locale,
 }
 
 impl BannerInstructionsFactory {
     pub fn new(next_step: Option<RouteStep>, step: Option<RouteStep>) -> Self {
-        BannerInstructionsFactory { next_step, step }
+        BannerInstructionsFactory {
+            next_step,
+            step,
+            step_after_next: None,
+            arrival: None,
+            locale: Locale::default(),
+        }
     }
 
+    /// Sets the step following the next step, previewed in a sub banner when the next step is short.
+    pub fn with_step_after_next(mut self, step_after_next: Option<RouteStep>) -> Self {
+        self.step_after_next = step_after_next;
+        self
+    }
+
+    /// Sets where the step's leg ends, used to name the stop or destination being approached.
+    pub fn with_arrival(mut self, arrival: Option<Arrival>) -> Self {
+        self.arrival = arrival;
+        self
+    }
+
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Builds the banner instructions in the OpenAPI model, which has no `sub` banner.
     pub fn build(&self) -> Vec<BannerInstruction> {
+        self.build_extended().into_iter().map(Into::into).collect()
+    }
+
+    /// Builds the banner instructions including the `sub` banner and lane components.
+    pub fn build_extended(&self) -> Vec<ExtendedBannerInstruction> {
         vec![self.generate()]
     }
 
-    fn generate(&self) -> BannerInstruction {
+    fn generate(&self) -> ExtendedBannerInstruction {
         self.next_step
             .as_ref()
             .map(|step| {
@@ -27,58 +68,124 @@ impl BannerInstructionsFactory {
                     .step
                     .clone()
                     .map_or(0.0, |step| step.distance.unwrap_or(0.0));
-                let maneuver = step.maneuver.as_ref().unwrap();
-                let name = step_maneuver_name(step.clone());
-
-                let component = BannerContentComponentsInner {
-                    r#type: Some("text".to_string()),
-                    text: Some(name.clone()),
-                };
-
-                let primary = BannerContent {
-                    text: name,
-                    r#type: maneuver.r#type,
-                    modifier: maneuver.modifier,
-                    components: Some(vec![component]),
-                };
-
-                BannerInstruction {
+                let primary = self.maneuver_content(step);
+
+                ExtendedBannerInstruction {
                     distance_along_geometry: length,
-                    primary: Box::new(primary),
-                    secondary: None,
+                    secondary: secondary_content(step, self.locale),
+                    primary,
+                    sub: self.sub_content(step),
                 }
             })
-            .unwrap_or(self.arrival())
+            .unwrap_or_else(|| self.arrival())
     }
 
-    fn arrival(&self) -> BannerInstruction {
-        let text = t!("arrive").to_string();
-
-        let component = BannerContentComponentsInner {
-            r#type: Some("text".to_string()),
-            text: Some(text.clone()),
-        };
-
-        let primary = BannerContent {
-            text,
-            r#type: Some(ManeuverType::Arrive),
-            modifier: None,
-            components: Some(vec![component]),
-        };
+    fn arrival(&self) -> ExtendedBannerInstruction {
+        let text = self
+            .arrival
+            .as_ref()
+            .and_then(|arrival| arrival.arrived(self.locale))
+            .map(|text| text.trim_end_matches('.').to_string())
+            .unwrap_or_else(|| t!("arrive", locale = self.locale.as_str()).to_string());
 
-        BannerInstruction {
+        ExtendedBannerInstruction {
             distance_along_geometry: 0.0,
-            primary: Box::new(primary),
+            primary: text_content(text, Some(ManeuverType::Arrive), None),
             secondary: None,
+            sub: None,
+        }
+    }
+
+    /// The banner content describing the maneuver at the start of a step. A step without a
+    /// maneuver, which [`crate::instructions::InstructionsFactory`] rejects, gets a banner without a type.
+    fn maneuver_content(&self, step: &RouteStep) -> ExtendedBannerContent {
+        let r#type = step.maneuver.as_ref().and_then(|m| m.r#type);
+        let modifier = step.maneuver.as_ref().and_then(|m| m.modifier);
+
+        if let (Some(ManeuverType::Arrive), Some(name)) = (r#type, self.arrival_name()) {
+            return text_content(name, r#type, modifier);
+        }
+
+        let components = step_banner_components(step, self.locale);
+        ExtendedBannerContent {
+            text: banner_components_text(&components),
+            r#type,
+            modifier,
+            components: Some(components),
         }
     }
+
+    /// Lane guidance for the next maneuver when the intersection has lanes. Otherwise previews
+    /// the maneuver after the next step when the next step is too short to show on its own.
+    fn sub_content(&self, next_step: &RouteStep) -> Option<ExtendedBannerContent> {
+        if let Some(lanes) = lane_components(self.step.as_ref(), next_step) {
+            let maneuver = next_step.maneuver.as_ref();
+            return Some(ExtendedBannerContent {
+                text: String::new(),
+                r#type: maneuver.and_then(|m| m.r#type),
+                modifier: maneuver.and_then(|m| m.modifier),
+                components: Some(lanes),
+            });
+        }
+
+        let is_short = next_step
+            .distance
+            .is_some_and(|distance| distance < SUB_BANNER_MAXIMUM_DISTANCE);
+
+        self.step_after_next
+            .as_ref()
+            .filter(|step| is_short && step.maneuver.is_some())
+            .map(|step| self.maneuver_content(step))
+    }
+
+    fn arrival_name(&self) -> Option<String> {
+        self.arrival
+            .as_ref()
+            .and_then(|arrival| arrival.name())
+            .map(str::to_string)
+    }
+}
+
+/// Secondary text from the step's signed destinations, e.g. "Toward Denver". The exit number
+/// is already part of the primary banner, and destinations the primary names the step by are
+/// not repeated.
+fn secondary_content(step: &RouteStep, locale: Locale) -> Option<ExtendedBannerContent> {
+    let destinations = step_other_destinations(step);
+    if destinations.is_empty() {
+        return None;
+    }
+
+    let text = t!(
+        "Toward %{destinations}",
+        locale = locale.as_str(),
+        destinations = destinations.join(", ")
+    );
+
+    Some(text_content(text.to_string(), None, None))
+}
+
+fn text_content(
+    text: String,
+    r#type: Option<ManeuverType>,
+    modifier: Option<ManeuverModifier>,
+) -> ExtendedBannerContent {
+    let component = ExtendedBannerComponent::new("text", text.clone());
+
+    ExtendedBannerContent {
+        text,
+        r#type,
+        modifier,
+        components: Some(vec![component]),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{POLYLINE_PRECISION, testing::load_route_steps};
     use insta::assert_json_snapshot;
     use osrm_openapi_models::models::{ManeuverModifier, ManeuverType, StepManeuver};
+    use serde_json::json;
 
     #[test]
     fn test_banner_with_next_step() {
@@ -108,6 +215,185 @@ mod tests {
         assert_json_snapshot!(factory.build());
     }
 
+    #[test]
+    fn test_banner_secondary_from_exits_and_destinations() {
+        let next_step = RouteStep {
+            distance: Some(2325.0),
+            maneuver: Some(Box::new(StepManeuver {
+                r#type: Some(ManeuverType::OffRamp),
+                modifier: Some(ManeuverModifier::SlightRight),
+                ..Default::default()
+            })),
+            name: Some("Interstate 70 Business Loop".to_string()),
+            r#ref: Some("I 70 Business".to_string()),
+            exits: Some(json!("37")),
+            destinations: Some(json!(
+                "I 70 Business Loop, US 6, US 50: Clifton, Grand Junction, Delta"
+            )),
+            ..Default::default()
+        };
+
+        let current_step = RouteStep {
+            distance: Some(7262.0),
+            ..Default::default()
+        };
+
+        let factory = BannerInstructionsFactory::new(Some(next_step), Some(current_step));
+        assert_json_snapshot!(factory.build());
+    }
+
+    #[test]
+    fn test_banner_secondary_skips_primary_destination() {
+        // Ramps without a name or ref use their destinations as the primary text.
+        let next_step = RouteStep {
+            distance: Some(607.0),
+            maneuver: Some(Box::new(StepManeuver {
+                r#type: Some(ManeuverType::OffRamp),
+                modifier: Some(ManeuverModifier::SlightRight),
+                ..Default::default()
+            })),
+            destinations: Some(json!("Indiana Street, Denver West")),
+            ..Default::default()
+        };
+
+        let factory = BannerInstructionsFactory::new(Some(next_step), None);
+        assert_eq!(factory.build()[0].secondary, None);
+    }
+
+    #[test]
+    fn test_banner_secondary_compares_parsed_signage() {
+        // Neither the exit "7" in "I 70" nor "Denver" in the street name is a repeat.
+        let next_step = RouteStep {
+            maneuver: Some(Box::new(StepManeuver {
+                r#type: Some(ManeuverType::OffRamp),
+                modifier: Some(ManeuverModifier::SlightRight),
+                ..Default::default()
+            })),
+            name: Some("Denver West Parkway".to_string()),
+            r#ref: Some("I 70".to_string()),
+            exits: Some(json!("7")),
+            destinations: Some(json!("Denver")),
+            ..Default::default()
+        };
+
+        let banner = &BannerInstructionsFactory::new(Some(next_step), None).build()[0];
+        assert_eq!(banner.primary.text, "Exit 7 I 70");
+        assert_eq!(
+            banner
+                .secondary
+                .as_ref()
+                .map(|secondary| secondary.text.as_str()),
+            Some("Toward Denver")
+        );
+
+        // A destination which is the ramp's own ref is a repeat.
+        let ramp = RouteStep {
+            maneuver: Some(Box::new(StepManeuver::default())),
+            r#ref: Some("I 70".to_string()),
+            destinations: Some(json!("I 70")),
+            ..Default::default()
+        };
+        let banner = &BannerInstructionsFactory::new(Some(ramp), None).build()[0];
+        assert_eq!(banner.secondary, None);
+    }
+
+    #[test]
+    fn test_banner_sub_for_short_next_step() {
+        let next_step = RouteStep {
+            distance: Some(21.0),
+            maneuver: Some(Box::new(StepManeuver {
+                r#type: Some(ManeuverType::EndOfRoad),
+                modifier: Some(ManeuverModifier::Left),
+                ..Default::default()
+            })),
+            name: Some("Southeast Stark Street".to_string()),
+            ..Default::default()
+        };
+
+        let step_after_next = RouteStep {
+            distance: Some(804.0),
+            maneuver: Some(Box::new(StepManeuver {
+                r#type: Some(ManeuverType::Turn),
+                modifier: Some(ManeuverModifier::Right),
+                ..Default::default()
+            })),
+            name: Some("Southeast 60th Avenue".to_string()),
+            ..Default::default()
+        };
+
+        let current_step = RouteStep {
+            distance: Some(1546.0),
+            ..Default::default()
+        };
+
+        let factory = BannerInstructionsFactory::new(Some(next_step), Some(current_step))
+            .with_step_after_next(Some(step_after_next));
+        assert_json_snapshot!(factory.build_extended());
+    }
+
+    #[test]
+    fn test_banner_no_sub_for_long_next_step() {
+        let next_step = RouteStep {
+            distance: Some(804.0),
+            maneuver: Some(Box::new(StepManeuver {
+                r#type: Some(ManeuverType::Turn),
+                modifier: Some(ManeuverModifier::Right),
+                ..Default::default()
+            })),
+            name: Some("Southeast 60th Avenue".to_string()),
+            ..Default::default()
+        };
+
+        let factory = BannerInstructionsFactory::new(Some(next_step.clone()), None)
+            .with_step_after_next(Some(next_step));
+        assert_eq!(factory.build_extended()[0].sub, None);
+    }
+
+    #[test]
+    fn test_banner_sub_lanes() {
+        let (current, next, after) =
+            load_route_steps("./fixtures/valhalla-ramps.json", 0, 2, POLYLINE_PRECISION);
+        let factory = BannerInstructionsFactory::new(next.map(|b| b.step), Some(current.step))
+            .with_step_after_next(after.map(|b| b.step));
+        assert_json_snapshot!(factory.build_extended());
+    }
+
+    #[test]
+    fn test_banner_arrival_at_stop() {
+        let factory =
+            BannerInstructionsFactory::new(None, None).with_arrival(Some(Arrival::Stop {
+                ordinal: 1,
+                name: Some("Elberta Avenue".to_string()),
+            }));
+        assert_json_snapshot!(factory.build());
+    }
+
+    #[test]
+    fn test_banner_approaching_stop() {
+        let next_step = RouteStep {
+            distance: Some(0.0),
+            maneuver: Some(Box::new(StepManeuver {
+                r#type: Some(ManeuverType::Arrive),
+                ..Default::default()
+            })),
+            name: Some("Patterson Road".to_string()),
+            r#ref: Some("F".to_string()),
+            ..Default::default()
+        };
+
+        let current_step = RouteStep {
+            distance: Some(409.0),
+            ..Default::default()
+        };
+
+        let factory = BannerInstructionsFactory::new(Some(next_step), Some(current_step))
+            .with_arrival(Some(Arrival::Stop {
+                ordinal: 1,
+                name: Some("Elberta Avenue".to_string()),
+            }));
+        assert_json_snapshot!(factory.build());
+    }
+
     #[test]
     fn test_banner_no_current_step_distance() {
         let next_step = RouteStep {
@@ -0,0 +1,232 @@
+use osrm_openapi_models::models::{Intersection, Lane, RouteStep};
+
+use crate::osrm::models::ExtendedBannerComponent;
+
+/// Builds `lane` banner components for the maneuver at the start of `next_step`.
+///
+/// Lanes come from the last intersection before the maneuver: the maneuver's own
+/// intersection (the first intersection of the next step), or failing that the last
+/// intersection of the current step. Returns `None` when neither carries lanes, or
+/// when no lane is valid for the maneuver.
+pub(crate) fn lane_components(
+    current_step: Option<&RouteStep>,
+    next_step: &RouteStep,
+) -> Option<Vec<ExtendedBannerComponent>> {
+    let maneuver_intersection = next_step
+        .intersections
+        .as_ref()
+        .and_then(|intersections| intersections.first());
+    let last_intersection = current_step
+        .and_then(|step| step.intersections.as_ref())
+        .and_then(|intersections| intersections.last());
+
+    let lanes = [maneuver_intersection, last_intersection]
+        .into_iter()
+        .flatten()
+        .find_map(intersection_lanes)?;
+
+    if !lanes.iter().any(|lane| lane.valid.unwrap_or(false)) {
+        return None;
+    }
+
+    let modifier = next_step
+        .maneuver
+        .as_ref()
+        .and_then(|m| m.modifier)
+        .map(|modifier| modifier.to_string());
+
+    // Lanes are only narrowed down by their indications when some lane is marked for the
+    // maneuver. Otherwise, e.g. when every lane is marked "straight" for a slight turn, OSRM's
+    // `valid` alone says which lanes can be used.
+    let any_marked = modifier.as_deref().is_some_and(|modifier| {
+        lanes
+            .iter()
+            .any(|lane| matching_indication(lane, modifier).is_some())
+    });
+
+    Some(
+        lanes
+            .iter()
+            .map(|lane| lane_component(lane, modifier.as_deref().filter(|_| any_marked)))
+            .collect(),
+    )
+}
+
+fn intersection_lanes(intersection: &Intersection) -> Option<&Vec<Lane>> {
+    intersection
+        .lanes
+        .as_ref()
+        .filter(|lanes| !lanes.is_empty())
+}
+
+/// Builds a lane component, marking it active when OSRM says the lane is valid for the route.
+/// With `modifier`, the lane must also be marked for the maneuver, or be unmarked.
+fn lane_component(lane: &Lane, modifier: Option<&str>) -> ExtendedBannerComponent {
+    let directions = lane.indications.clone().unwrap_or_default();
+    let valid = lane.valid.unwrap_or(false);
+
+    let matching = modifier.and_then(|modifier| matching_indication(lane, modifier));
+    let unmarked = directions.iter().all(|direction| direction == "none");
+    let active = valid && (modifier.is_none() || matching.is_some() || unmarked);
+    let active_direction = matching.filter(|_| active).map(str::to_string);
+
+    ExtendedBannerComponent {
+        text: Some(String::new()),
+        r#type: Some("lane".to_string()),
+        directions: Some(directions),
+        active: Some(active),
+        active_direction,
+    }
+}
+
+/// The lane's indication leading the way of `modifier`, where slight and sharp turns count as
+/// turns to the same side, e.g. "right" for a "slight right" maneuver.
+fn matching_indication<'a>(lane: &'a Lane, modifier: &str) -> Option<&'a str> {
+    lane.indications
+        .iter()
+        .flatten()
+        .map(String::as_str)
+        .find(|indication| base_direction(indication) == base_direction(modifier))
+}
+
+/// The direction without "slight" or "sharp", e.g. "right" for "sharp right".
+fn base_direction(direction: &str) -> &str {
+    direction
+        .strip_prefix("slight ")
+        .or_else(|| direction.strip_prefix("sharp "))
+        .unwrap_or(direction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{POLYLINE_PRECISION, testing::load_route_steps};
+    use insta::assert_json_snapshot;
+    use osrm_openapi_models::models::{ManeuverModifier, StepManeuver};
+
+    #[test]
+    fn test_lane_components_off_ramp() {
+        // Step 2 of the ramps fixture ends at an off ramp whose intersection has three lanes.
+        let (current, next, _) =
+            load_route_steps("./fixtures/valhalla-ramps.json", 0, 2, POLYLINE_PRECISION);
+        let lanes = lane_components(Some(&current.step), &next.unwrap().step);
+        assert_json_snapshot!(lanes);
+    }
+
+    #[test]
+    fn test_lane_components_without_lanes() {
+        let (current, next, _) =
+            load_route_steps("./fixtures/valhalla-ramps.json", 0, 0, POLYLINE_PRECISION);
+        assert_eq!(
+            lane_components(Some(&current.step), &next.unwrap().step),
+            None
+        );
+    }
+
+    fn lane(indications: &[&str], valid: bool) -> Lane {
+        Lane {
+            indications: Some(indications.iter().map(|i| i.to_string()).collect()),
+            valid: Some(valid),
+        }
+    }
+
+    fn active(lanes: Vec<Lane>, modifier: Option<ManeuverModifier>) -> Vec<(bool, Option<String>)> {
+        let next_step = RouteStep {
+            maneuver: Some(Box::new(StepManeuver {
+                modifier,
+                ..Default::default()
+            })),
+            intersections: Some(vec![Intersection {
+                lanes: Some(lanes),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        lane_components(None, &next_step)
+            .unwrap()
+            .into_iter()
+            .map(|lane| (lane.active.unwrap(), lane.active_direction))
+            .collect()
+    }
+
+    #[test]
+    fn test_lane_components_active_for_modifier() {
+        // Both lanes are valid, but only the one leading right is active for a right turn.
+        assert_eq!(
+            active(
+                vec![lane(&["left"], true), lane(&["straight", "right"], true)],
+                Some(ManeuverModifier::Right)
+            ),
+            vec![(false, None), (true, Some("right".to_string()))]
+        );
+        // Without a modifier the lanes OSRM says are valid are active.
+        assert_eq!(
+            active(vec![lane(&["left"], true), lane(&["right"], false)], None),
+            vec![(true, None), (false, None)]
+        );
+    }
+
+    #[test]
+    fn test_lane_components_invalid_matching_lane() {
+        // A lane marked for the maneuver which OSRM says can't be used is not active.
+        assert_eq!(
+            active(
+                vec![lane(&["right"], false), lane(&["right"], true)],
+                Some(ManeuverModifier::Right)
+            ),
+            vec![(false, None), (true, Some("right".to_string()))]
+        );
+    }
+
+    #[test]
+    fn test_lane_components_slight_and_unmarked_lanes() {
+        // Slight and sharp turns match lanes marked for the same side, by their own indication.
+        assert_eq!(
+            active(
+                vec![
+                    lane(&["straight"], true),
+                    lane(&["right"], true),
+                    lane(&["slight right"], true)
+                ],
+                Some(ManeuverModifier::SlightRight)
+            ),
+            vec![
+                (false, None),
+                (true, Some("right".to_string())),
+                (true, Some("slight right".to_string()))
+            ]
+        );
+        // Unmarked lanes can be used in any direction.
+        assert_eq!(
+            active(
+                vec![lane(&["none"], true), lane(&["sharp right"], true)],
+                Some(ManeuverModifier::SlightRight)
+            ),
+            vec![(true, None), (true, Some("sharp right".to_string()))]
+        );
+        // When no lane is marked for the maneuver, `valid` alone decides.
+        assert_eq!(
+            active(
+                vec![lane(&["straight"], true), lane(&["left"], false)],
+                Some(ManeuverModifier::SlightRight)
+            ),
+            vec![(true, None), (false, None)]
+        );
+    }
+
+    #[test]
+    fn test_lane_components_no_valid_lanes() {
+        let lane = Lane {
+            indications: Some(vec!["left".to_string()]),
+            valid: Some(false),
+        };
+        let next_step = RouteStep {
+            intersections: Some(vec![Intersection {
+                lanes: Some(vec![lane]),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        assert_eq!(lane_components(None, &next_step), None);
+    }
+}
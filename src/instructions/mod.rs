@@ -1,79 +1,416 @@
+use arrival::Arrival;
 use banner_instruction::BannerInstructionsFactory;
-use osrm_openapi_models::models::Route;
+use osrm_openapi_models::models::{Route, VoiceInstruction, Waypoint};
+use serde_json::Value;
+use ssml::SsmlOptions;
+use voice_announcements::AnnouncementDecision;
 use voice_instructions::VoiceInstructionFactory;
 
-use crate::osrm::utilities::get_step_bundles;
+use crate::{
+    Error,
+    distance::UnitSystem,
+    locale::Locale,
+    osrm::{
+        IntersectionPosition, RouteStepBundle,
+        models::{ExtendedBannerInstruction, ExtendedRoute, ExtendedRouteLeg},
+        utilities::{detect_polyline_precision, get_step_bundles, snap_intersections},
+    },
+};
 
+pub mod arrival;
 pub mod banner_instruction;
+pub mod lanes;
 pub mod speed_class;
+pub mod ssml;
 pub mod utilities;
 pub mod voice_announcements;
 pub mod voice_instructions;
 
+#[derive(Clone)]
 pub struct InstructionsFactory {
     polyline_precision: u32,
-    metric: bool,
+    automatic_polyline_precision: bool,
+    unit_system: UnitSystem,
+    waypoints: Vec<Waypoint>,
+    ssml_options: SsmlOptions,
+    locale: Locale,
+    automatic_unit_system: bool,
+    speed_limit_announcements: bool,
+    validate_intersections: bool,
 }
 
 impl InstructionsFactory {
-    pub fn new(polyline_precision: u32, metric: bool) -> Self {
+    pub fn new(polyline_precision: u32, unit_system: UnitSystem) -> Self {
         InstructionsFactory {
             polyline_precision,
-            metric,
+            automatic_polyline_precision: false,
+            unit_system,
+            waypoints: Vec::new(),
+            ssml_options: SsmlOptions::default(),
+            locale: Locale::default(),
+            automatic_unit_system: false,
+            speed_limit_announcements: false,
+            validate_intersections: false,
         }
     }
 
-    pub fn apply(&self, mut route: Route) -> Option<Route> {
-        let step_bundles = get_step_bundles(&route, self.polyline_precision)?;
-        let legs = route.legs.as_mut()?;
+    /// Provides the response's waypoints so arrivals can name each stop and the destination.
+    pub fn with_waypoints(mut self, waypoints: Vec<Waypoint>) -> Self {
+        self.waypoints = waypoints;
+        self
+    }
+
+    /// Sets the options applied to the SSML voice announcements.
+    pub fn with_ssml_options(mut self, ssml_options: SsmlOptions) -> Self {
+        self.ssml_options = ssml_options;
+        self
+    }
+
+    /// Sets the language of the generated voice and banner instructions.
+    ///
+    /// The server's maneuver instructions are taken to be in English, so other locales announce
+    /// maneuvers composed from their type, modifier and street name instead.
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Announces where the speed limit changes along a step, from the legs' `maxspeed`
+    /// annotations.
+    pub fn with_speed_limit_announcements(mut self) -> Self {
+        self.speed_limit_announcements = true;
+        self
+    }
+
+    /// Picks each step's unit system from its speed limit unit and country, so a route crossing
+    /// a border is announced in the units signed on each side of it. See [`UnitSystem::for_step`].
+    ///
+    /// The OpenAPI model drops these fields, so only [`InstructionsFactory::apply_extended`],
+    /// [`InstructionsFactory::apply_to_json`] and [`InstructionsFactory::explain_json`] can read
+    /// them. Steps carrying neither fall back to the unit system the factory was created with.
+    pub fn with_automatic_unit_system(mut self) -> Self {
+        self.automatic_unit_system = true;
+        self
+    }
+
+    /// Detects each route's polyline precision from its geometry, keeping the precision the
+    /// factory was created with when it fits. Routes fitting neither 5 nor 6 fail with
+    /// [`Error::UnknownPolylinePrecision`].
+    pub fn with_automatic_polyline_precision(mut self) -> Self {
+        self.automatic_polyline_precision = true;
+        self
+    }
+
+    /// The precision `route`'s polylines are decoded with.
+    fn route_polyline_precision(&self, route: &Route) -> Result<u32, Error> {
+        if self.automatic_polyline_precision {
+            detect_polyline_precision(route, self.polyline_precision)
+        } else {
+            Ok(self.polyline_precision)
+        }
+    }
+
+    /// Cross-checks each step's intersections against its geometry, failing with
+    /// [`Error::IntersectionIndexMismatch`] when an intersection's `geometry_index` is not the
+    /// step coordinate at its location.
+    ///
+    /// The OpenAPI model drops `geometry_index`, so only [`InstructionsFactory::apply_extended`],
+    /// [`InstructionsFactory::apply_to_json`] and [`InstructionsFactory::explain_json`] can read
+    /// it, and routes without extended legs aren't checked. Extended legs or steps that don't
+    /// match the route's fail with [`Error::LegCountMismatch`] or [`Error::StepCountMismatch`].
+    pub fn with_intersection_validation(mut self) -> Self {
+        self.validate_intersections = true;
+        self
+    }
 
-        // Process each leg's steps
-        for leg in legs.iter_mut() {
-            // Access steps through the leg's steps field
-            if let Some(ref mut steps) = leg.steps {
-                for (index, step) in steps.iter_mut().enumerate() {
-                    let current = step_bundles.get(index)?;
-                    let next = step_bundles.get(index + 1);
-                    let step_after_next = step_bundles.get(index + 2);
+    /// The unit system announcements on the step at `step_index` of the leg at `leg_index` are
+    /// made in.
+    fn step_unit_system(
+        &self,
+        legs: Option<&[ExtendedRouteLeg]>,
+        leg_index: usize,
+        step_index: usize,
+    ) -> UnitSystem {
+        legs.filter(|_| self.automatic_unit_system)
+            .and_then(|legs| legs.get(leg_index))
+            .and_then(|leg| UnitSystem::for_step(leg, step_index))
+            .unwrap_or(self.unit_system)
+    }
+
+    /// The legs of a route's JSON with the fields the OpenAPI model drops, when an option needs
+    /// them.
+    fn extended_legs(&self, route: &Value) -> Result<Option<Vec<ExtendedRouteLeg>>, Error> {
+        if !self.automatic_unit_system && !self.validate_intersections {
+            return Ok(None);
+        }
+
+        let extended: ExtendedRoute = serde_json::from_value(route.clone())
+            .map_err(|error| Error::InvalidJson(error.to_string()))?;
+        Ok(Some(extended.legs.unwrap_or_default()))
+    }
+
+    /// Adds banner and voice instructions to every step of `route`.
+    ///
+    /// The OpenAPI model has no `sub` banner and no lane details on banner components, so these
+    /// are dropped. Use [`InstructionsFactory::apply_extended`] or
+    /// [`InstructionsFactory::apply_to_json`] to keep them.
+    pub fn apply(&self, mut route: Route) -> Result<Route, Error> {
+        self.apply_in_place(&mut route)?;
+        Ok(route)
+    }
+
+    /// Adds banner and voice instructions to every step of `route` in place.
+    ///
+    /// The instructions are built before any step is changed, so on error `route` is left as it was.
+    /// Like [`InstructionsFactory::apply`], this drops `sub` banners and lane details.
+    pub fn apply_in_place(&self, route: &mut Route) -> Result<(), Error> {
+        let instructions = self.build(route, None)?;
 
-                    // Generate banner instructions
-                    let banner_factory = BannerInstructionsFactory::new(
-                        next.map(|b| b.step.clone()),
-                        Some(current.step.clone()),
+        let legs = route.legs.iter_mut().flatten();
+        for (leg, leg_instructions) in legs.zip(instructions) {
+            let steps = leg.steps.iter_mut().flatten();
+            for (step, instructions) in steps.zip(leg_instructions) {
+                step.banner_instructions = Some(
+                    instructions
+                        .banner_instructions
+                        .into_iter()
+                        .map(Into::into)
+                        .collect(),
+                );
+                if instructions.voice_instructions.is_some() {
+                    step.voice_instructions = instructions.voice_instructions;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds the banner and voice instructions of every step of `route` to `extended`, keeping
+    /// the `sub` banners and lane details the OpenAPI model drops.
+    ///
+    /// `extended` is read from the same route JSON, or is `ExtendedRoute::default()` when there is
+    /// none. Legs and steps it is missing are added. On error `extended` is left as it was.
+    pub fn apply_extended(&self, route: &Route, extended: &mut ExtendedRoute) -> Result<(), Error> {
+        let instructions = self.build(route, extended.legs.as_deref())?;
+
+        let legs = extended.legs.get_or_insert_with(Vec::new);
+        legs.resize_with(legs.len().max(instructions.len()), Default::default);
+        for (leg, leg_instructions) in legs.iter_mut().zip(instructions) {
+            let steps = leg.steps.get_or_insert_with(Vec::new);
+            steps.resize_with(steps.len().max(leg_instructions.len()), Default::default);
+            for (step, instructions) in steps.iter_mut().zip(leg_instructions) {
+                step.banner_instructions = Some(instructions.banner_instructions);
+                if instructions.voice_instructions.is_some() {
+                    step.voice_instructions = instructions.voice_instructions;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds banner and voice instructions to every step of a route's JSON in place.
+    ///
+    /// Only `bannerInstructions` and `voiceInstructions` are written, so fields the OpenAPI model
+    /// doesn't describe are kept, and the banners include their `sub` banner and lanes. On error
+    /// `route` is left as it was.
+    pub fn apply_to_json(&self, route: &mut Value) -> Result<(), Error> {
+        let typed: Route = serde_json::from_value(route.clone())
+            .map_err(|error| Error::InvalidJson(error.to_string()))?;
+        let legs = self.extended_legs(route)?;
+        let instructions = self.build(&typed, legs.as_deref())?;
+
+        let legs = route
+            .get_mut("legs")
+            .and_then(Value::as_array_mut)
+            .ok_or(Error::MissingLegs)?;
+        for (leg, leg_instructions) in legs.iter_mut().zip(instructions) {
+            let Some(steps) = leg.get_mut("steps").and_then(Value::as_array_mut) else {
+                continue;
+            };
+            for (step, instructions) in steps.iter_mut().zip(leg_instructions) {
+                let Some(step) = step.as_object_mut() else {
+                    continue;
+                };
+                step.insert(
+                    "bannerInstructions".to_string(),
+                    serde_json::to_value(instructions.banner_instructions)
+                        .map_err(|error| Error::InvalidJson(error.to_string()))?,
+                );
+                if let Some(voice_instructions) = instructions.voice_instructions {
+                    step.insert(
+                        "voiceInstructions".to_string(),
+                        serde_json::to_value(voice_instructions)
+                            .map_err(|error| Error::InvalidJson(error.to_string()))?,
                     );
-                    step.banner_instructions = Some(banner_factory.build());
-
-                    // Generate voice instructions if we have a next step
-                    if let Some(next_bundle) = next {
-                        let voice_factory = VoiceInstructionFactory::new(
-                            current.clone(),
-                            next_bundle.clone(),
-                            step_after_next.cloned(),
-                            self.metric,
-                            self.polyline_precision,
-                        );
-                        step.voice_instructions = Some(voice_factory.build());
-                    }
                 }
             }
         }
 
-        Some(route)
+        Ok(())
+    }
+
+    /// Explains the instructions of every step of `route`, indexed by `[leg][step]`.
+    pub fn explain(&self, route: &Route) -> Result<Vec<Vec<StepExplanation>>, Error> {
+        self.explain_steps(route, None)
+    }
+
+    /// Explains the instructions [`InstructionsFactory::apply_to_json`] would add to every step of
+    /// a route's JSON, indexed by `[leg][step]`.
+    pub fn explain_json(&self, route: &Value) -> Result<Vec<Vec<StepExplanation>>, Error> {
+        let typed: Route = serde_json::from_value(route.clone())
+            .map_err(|error| Error::InvalidJson(error.to_string()))?;
+        let legs = self.extended_legs(route)?;
+        self.explain_steps(&typed, legs.as_deref())
+    }
+
+    fn explain_steps(
+        &self,
+        route: &Route,
+        legs: Option<&[ExtendedRouteLeg]>,
+    ) -> Result<Vec<Vec<StepExplanation>>, Error> {
+        self.map_steps(
+            route,
+            legs,
+            |current, unit_system, banner_factory, voice_factory| StepExplanation {
+                leg_index: current.leg_index,
+                step_index: current.step_index,
+                instruction: current
+                    .step
+                    .maneuver
+                    .as_ref()
+                    .and_then(|maneuver| maneuver.instruction.clone()),
+                distance: current.step.distance.unwrap_or(0.0),
+                unit_system,
+                intersections: current.intersections.clone(),
+                banner_instructions: banner_factory.build_extended(),
+                announcements: voice_factory.map(|f| f.explain()).unwrap_or_default(),
+            },
+        )
+    }
+
+    /// Builds the instructions of every step of `route`, indexed by `[leg][step]`, reading the
+    /// fields the OpenAPI model drops from `legs`.
+    fn build(
+        &self,
+        route: &Route,
+        legs: Option<&[ExtendedRouteLeg]>,
+    ) -> Result<Vec<Vec<StepInstructions>>, Error> {
+        self.map_steps(route, legs, |_, _, banner_factory, voice_factory| {
+            StepInstructions {
+                banner_instructions: banner_factory.build_extended(),
+                voice_instructions: voice_factory.map(|f| f.build()),
+            }
+        })
+    }
+
+    /// Maps every step of `route` with the factories of its banner and voice instructions. Steps
+    /// without a next step have no voice instructions.
+    fn map_steps<T>(
+        &self,
+        route: &Route,
+        legs: Option<&[ExtendedRouteLeg]>,
+        map: impl Fn(
+            &RouteStepBundle,
+            UnitSystem,
+            BannerInstructionsFactory,
+            Option<VoiceInstructionFactory>,
+        ) -> T,
+    ) -> Result<Vec<Vec<T>>, Error> {
+        let polyline_precision = self.route_polyline_precision(route)?;
+        let mut step_bundles = get_step_bundles(route, polyline_precision)?;
+        if let Some(legs) = legs.filter(|_| self.validate_intersections) {
+            snap_intersections(&mut step_bundles, legs, polyline_precision)?;
+        }
+        let arrivals = Arrival::for_legs(route, &self.waypoints);
+
+        // Map each leg's steps against that leg's own bundles
+        let steps = step_bundles
+            .iter()
+            .zip(arrivals)
+            .enumerate()
+            .map(|(leg_index, (leg_bundles, arrival))| {
+                leg_bundles
+                    .iter()
+                    .enumerate()
+                    .map(|(index, current)| {
+                        let unit_system =
+                            self.step_unit_system(legs, leg_index, current.step_index);
+                        let next = leg_bundles.get(index + 1);
+                        let step_after_next = leg_bundles.get(index + 2);
+
+                        let banner_factory = BannerInstructionsFactory::new(
+                            next.map(|b| b.step.clone()),
+                            Some(current.step.clone()),
+                        )
+                        .with_step_after_next(step_after_next.map(|b| b.step.clone()))
+                        .with_arrival(Some(arrival.clone()))
+                        .with_locale(self.locale);
+
+                        let voice_factory = next.map(|next_bundle| {
+                            VoiceInstructionFactory::new(
+                                current.clone(),
+                                next_bundle.clone(),
+                                step_after_next.cloned(),
+                                unit_system,
+                                polyline_precision,
+                            )
+                            .with_arrival(Some(arrival.clone()))
+                            .with_ssml_options(self.ssml_options.clone())
+                            .with_locale(self.locale)
+                            .with_speed_limit_announcements(self.speed_limit_announcements)
+                        });
+
+                        map(current, unit_system, banner_factory, voice_factory)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(steps)
     }
 }
 
+/// The instructions built for one step.
+struct StepInstructions {
+    banner_instructions: Vec<ExtendedBannerInstruction>,
+    voice_instructions: Option<Vec<VoiceInstruction>>,
+}
+
+/// The instructions of one step, with the decision behind each type of voice announcement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepExplanation {
+    pub leg_index: usize,
+    pub step_index: usize,
+    /// The server's instruction for the step's maneuver.
+    pub instruction: Option<String>,
+    /// Length of the step in meters.
+    pub distance: f64,
+    pub unit_system: UnitSystem,
+    /// Where the step's intersections are, when checked with
+    /// [`InstructionsFactory::with_intersection_validation`].
+    pub intersections: Vec<IntersectionPosition>,
+    pub banner_instructions: Vec<ExtendedBannerInstruction>,
+    pub announcements: Vec<(AnnouncementDecision, Option<VoiceInstruction>)>,
+}
+
 #[cfg(test)]
 mod tests {
     use crate::POLYLINE_PRECISION;
 
     use super::*;
-    use crate::testing::load_route;
+    use crate::osrm::models::{Admin, ExtendedRouteStep};
+    use crate::testing::{
+        fixtures::{load_extended_route, load_route_response},
+        load_route,
+    };
     use insta::assert_json_snapshot;
 
     #[test]
     fn test_instructions_factory() {
         let route = load_route("./fixtures/valhalla-short.json", 0);
-        let factory = InstructionsFactory::new(POLYLINE_PRECISION, true);
+        let factory = InstructionsFactory::new(POLYLINE_PRECISION, UnitSystem::Metric);
         let route_with_instructions = factory.apply(route).unwrap();
         assert_json_snapshot!(route_with_instructions);
     }
@@ -81,8 +418,328 @@ mod tests {
     #[test]
     fn test_instructions_factory_imperial() {
         let route = load_route("./fixtures/valhalla-short.json", 0);
-        let factory = InstructionsFactory::new(POLYLINE_PRECISION, false);
+        let factory = InstructionsFactory::new(POLYLINE_PRECISION, UnitSystem::ImperialUs);
         let route_with_instructions = factory.apply(route).unwrap();
         assert_json_snapshot!(route_with_instructions);
     }
+
+    #[test]
+    fn test_instructions_factory_geojson() {
+        use crate::osrm::RouteStepExt;
+
+        let polyline = load_route("./fixtures/valhalla-short.json", 0);
+        let mut geojson = polyline.clone();
+        for leg in geojson.legs.iter_mut().flatten() {
+            for step in leg.steps.iter_mut().flatten() {
+                let coordinates = step
+                    .geometry_coordinates(POLYLINE_PRECISION)
+                    .unwrap()
+                    .unwrap()
+                    .into_iter()
+                    .map(|coord| vec![coord.x, coord.y])
+                    .collect::<Vec<_>>();
+                step.geometry = Some(serde_json::json!({
+                    "type": "LineString",
+                    "coordinates": coordinates
+                }));
+            }
+        }
+
+        let factory = InstructionsFactory::new(POLYLINE_PRECISION, UnitSystem::Metric);
+        let instructions = |route: Route| {
+            factory
+                .apply(route)
+                .unwrap()
+                .legs
+                .unwrap()
+                .into_iter()
+                .flat_map(|leg| leg.steps.unwrap())
+                .map(|step| (step.banner_instructions, step.voice_instructions))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(instructions(geojson), instructions(polyline));
+    }
+
+    #[test]
+    fn test_instructions_factory_apply_extended() {
+        let route = load_route("./fixtures/valhalla-ramps.json", 0);
+        let factory = InstructionsFactory::new(POLYLINE_PRECISION, UnitSystem::Metric);
+
+        let mut extended = ExtendedRoute::default();
+        factory.apply_extended(&route, &mut extended).unwrap();
+        let steps = extended.legs.unwrap().remove(0).steps.unwrap();
+        let banners = steps
+            .iter()
+            .flat_map(|step| step.banner_instructions.clone().unwrap())
+            .collect::<Vec<_>>();
+
+        // The typed route gets the same banners, less what the OpenAPI model drops.
+        assert!(banners.iter().any(|banner| banner.sub.is_some()));
+        let lanes = banners
+            .iter()
+            .flat_map(|banner| banner.sub.iter())
+            .flat_map(|sub| sub.components.iter().flatten())
+            .filter(|component| component.r#type.as_deref() == Some("lane"))
+            .collect::<Vec<_>>();
+        assert!(!lanes.is_empty());
+        assert!(lanes.iter().all(|lane| lane.directions.is_some()));
+        assert!(lanes.iter().any(|lane| lane.active == Some(true)));
+        let typed = factory
+            .apply(route)
+            .unwrap()
+            .legs
+            .unwrap()
+            .remove(0)
+            .steps
+            .unwrap();
+        assert_eq!(
+            typed
+                .into_iter()
+                .flat_map(|step| step.banner_instructions.unwrap())
+                .collect::<Vec<_>>(),
+            banners.into_iter().map(Into::into).collect::<Vec<_>>()
+        );
+
+        // Reading the route's JSON into the extended model gets the banners of apply_to_json.
+        let response: Value = serde_json::from_str(
+            &std::fs::read_to_string("./fixtures/valhalla-ramps.json").unwrap(),
+        )
+        .unwrap();
+        let mut json = response["routes"][0].clone();
+        factory.apply_to_json(&mut json).unwrap();
+        let from_json: ExtendedRoute = serde_json::from_value(json).unwrap();
+        let banners_of = |steps: &[ExtendedRouteStep]| {
+            steps
+                .iter()
+                .map(|step| step.banner_instructions.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            banners_of(from_json.legs.unwrap()[0].steps.as_ref().unwrap()),
+            banners_of(&steps)
+        );
+    }
+
+    #[test]
+    fn test_instructions_factory_error() {
+        let mut route = load_route("./fixtures/valhalla-short.json", 0);
+        route.legs.as_mut().unwrap()[0].steps.as_mut().unwrap()[1].geometry =
+            Some(serde_json::json!({"type": "Point", "coordinates": [-108.365587, 39.115856]}));
+
+        let factory = InstructionsFactory::new(POLYLINE_PRECISION, UnitSystem::Metric);
+        assert_eq!(
+            factory.apply(route),
+            Err(Error::UnsupportedGeoJson { field: "geometry" }.in_step(0, 1))
+        );
+        assert_eq!(factory.apply(Route::default()), Err(Error::MissingLegs));
+
+        // A next step without a maneuver is an error rather than a panic.
+        let mut route = load_route("./fixtures/valhalla-short.json", 0);
+        route.legs.as_mut().unwrap()[0].steps.as_mut().unwrap()[2].maneuver = None;
+        assert_eq!(
+            factory.apply(route),
+            Err(Error::MissingManeuver.in_step(0, 2))
+        );
+    }
+
+    #[test]
+    fn test_instructions_factory_automatic_polyline_precision() {
+        let route = load_route("./fixtures/valhalla-short.json", 0);
+        let expected = InstructionsFactory::new(POLYLINE_PRECISION, UnitSystem::Metric)
+            .apply(route.clone())
+            .unwrap();
+
+        // The route is encoded at precision 6, so the factory's 5 doesn't fit it.
+        let factory =
+            InstructionsFactory::new(5, UnitSystem::Metric).with_automatic_polyline_precision();
+        assert_eq!(factory.apply(route.clone()), Ok(expected));
+
+        let mut far = route;
+        far.legs.as_mut().unwrap()[0].steps.as_mut().unwrap()[1]
+            .maneuver
+            .as_mut()
+            .unwrap()
+            .location = Some(vec![-104.99, 39.74]);
+        assert_eq!(factory.apply(far), Err(Error::UnknownPolylinePrecision));
+    }
+
+    #[test]
+    fn test_instructions_factory_multi_leg() {
+        let route = load_route("./fixtures/valhalla-legs.json", 0);
+        let factory = InstructionsFactory::new(POLYLINE_PRECISION, UnitSystem::Metric);
+        let route_with_instructions = factory.apply(route).unwrap();
+        assert_json_snapshot!(route_with_instructions);
+    }
+
+    #[test]
+    fn test_instructions_factory_multi_leg_waypoints() {
+        let waypoints = load_route_response("./fixtures/valhalla-legs.json")
+            .waypoints
+            .unwrap();
+        let route = load_route("./fixtures/valhalla-legs.json", 0);
+        let factory = InstructionsFactory::new(POLYLINE_PRECISION, UnitSystem::Metric)
+            .with_waypoints(waypoints);
+        let route_with_instructions = factory.apply(route).unwrap();
+        assert_json_snapshot!(route_with_instructions);
+    }
+
+    #[test]
+    fn test_instructions_factory_multi_leg_matches_single_leg() {
+        // The second leg of the split fixture covers the same steps as the tail of the single leg
+        // route, so its voice instructions for those steps must be computed from the same steps.
+        let factory = InstructionsFactory::new(POLYLINE_PRECISION, UnitSystem::Metric);
+        let single = factory
+            .apply(load_route("./fixtures/valhalla-short.json", 0))
+            .unwrap();
+        let multi = factory
+            .apply(load_route("./fixtures/valhalla-legs.json", 0))
+            .unwrap();
+
+        let single_steps = single.legs.unwrap()[0].steps.clone().unwrap();
+        let second_leg_steps = multi.legs.unwrap()[1].steps.clone().unwrap();
+
+        assert_eq!(
+            single_steps[3].voice_instructions,
+            second_leg_steps[1].voice_instructions
+        );
+        assert_eq!(
+            single_steps[3].banner_instructions,
+            second_leg_steps[1].banner_instructions
+        );
+    }
+
+    #[test]
+    fn test_instructions_factory_localized() {
+        let waypoints = load_route_response("./fixtures/valhalla-legs.json")
+            .waypoints
+            .unwrap();
+
+        for locale in [Locale::De, Locale::Fr, Locale::Es] {
+            let route = load_route("./fixtures/valhalla-legs.json", 0);
+            let factory = InstructionsFactory::new(POLYLINE_PRECISION, UnitSystem::Metric)
+                .with_waypoints(waypoints.clone())
+                .with_locale(locale);
+            let route_with_instructions = factory.apply(route).unwrap();
+
+            let announcements = route_with_instructions
+                .legs
+                .unwrap()
+                .into_iter()
+                .flat_map(|leg| leg.steps.unwrap())
+                .flat_map(|step| step.voice_instructions.unwrap_or_default())
+                .map(|instruction| instruction.announcement)
+                .collect::<Vec<_>>();
+
+            insta::with_settings!({ snapshot_suffix => locale.as_str() }, {
+                assert_json_snapshot!(announcements);
+            });
+        }
+    }
+
+    #[test]
+    fn test_instructions_factory_automatic_unit_system() {
+        // The fixture is in the US with speed limits in mph, so it matches the imperial output.
+        let route = load_route("./fixtures/valhalla-short.json", 0);
+        let mut automatic = load_extended_route("./fixtures/valhalla-short.json", 0);
+        InstructionsFactory::new(POLYLINE_PRECISION, UnitSystem::Metric)
+            .with_automatic_unit_system()
+            .apply_extended(&route, &mut automatic)
+            .unwrap();
+        let mut imperial = load_extended_route("./fixtures/valhalla-short.json", 0);
+        InstructionsFactory::new(POLYLINE_PRECISION, UnitSystem::ImperialUs)
+            .apply_extended(&route, &mut imperial)
+            .unwrap();
+        assert_eq!(automatic, imperial);
+
+        // The typed route has no speed limit units or countries to read.
+        assert_eq!(
+            InstructionsFactory::new(POLYLINE_PRECISION, UnitSystem::Metric)
+                .with_automatic_unit_system()
+                .apply(route.clone()),
+            InstructionsFactory::new(POLYLINE_PRECISION, UnitSystem::Metric).apply(route)
+        );
+    }
+
+    #[test]
+    fn test_instructions_factory_intersection_validation() {
+        let factory = InstructionsFactory::new(POLYLINE_PRECISION, UnitSystem::Metric)
+            .with_intersection_validation();
+
+        // The same factory checks each route against its own intersections.
+        for path in [
+            "./fixtures/valhalla-short.json",
+            "./fixtures/valhalla-legs.json",
+        ] {
+            let mut extended = load_extended_route(path, 0);
+            factory
+                .apply_extended(&load_route(path, 0), &mut extended)
+                .unwrap();
+        }
+
+        // A typed route has no intersections to check, and an extended route with too few legs
+        // fails instead of being checked against the wrong ones.
+        let route = load_route("./fixtures/valhalla-legs.json", 0);
+        assert_eq!(
+            factory.apply(route.clone()),
+            InstructionsFactory::new(POLYLINE_PRECISION, UnitSystem::Metric).apply(route.clone())
+        );
+        factory
+            .apply_extended(&route, &mut ExtendedRoute::default())
+            .unwrap();
+        let mut extended = load_extended_route("./fixtures/valhalla-legs.json", 0);
+        extended.legs.as_mut().unwrap().pop();
+        assert_eq!(
+            factory.apply_extended(&route, &mut extended),
+            Err(Error::LegCountMismatch {
+                expected: 2,
+                actual: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_instructions_factory_automatic_unit_system_border() {
+        // Move the second leg across the border into Canada after the exit from I 70.
+        let mut extended = load_extended_route("./fixtures/valhalla-legs.json", 0);
+        let leg = &mut extended.legs.as_mut().unwrap()[1];
+        leg.admins.as_mut().unwrap().push(Admin {
+            iso_3166_1: Some("CA".to_string()),
+            iso_3166_1_alpha3: Some("CAN".to_string()),
+        });
+        for step in leg.steps.iter_mut().flatten().skip(1) {
+            step.speed_limit_unit = Some("km/h".to_string());
+            for intersection in step.intersections.iter_mut().flatten() {
+                intersection.admin_index = Some(1);
+            }
+        }
+
+        let route = load_route("./fixtures/valhalla-legs.json", 0);
+        InstructionsFactory::new(POLYLINE_PRECISION, UnitSystem::ImperialUk)
+            .with_automatic_unit_system()
+            .apply_extended(&route, &mut extended)
+            .unwrap();
+
+        let announcements = extended.legs.unwrap()[1]
+            .steps
+            .clone()
+            .unwrap()
+            .into_iter()
+            .map(|step| {
+                step.voice_instructions
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|instruction| instruction.announcement)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        // The US step is announced in miles, the Canadian ones in kilometers.
+        let mentions = |step: &[String], unit: &str| step.iter().any(|a| a.contains(unit));
+        assert!(mentions(&announcements[0], "mile"));
+        assert!(!mentions(&announcements[0], "kilometer"));
+        assert!(mentions(&announcements[1], "kilometer"));
+        assert!(!mentions(&announcements[1], "mile"));
+        assert_json_snapshot!(announcements);
+    }
 }
@@ -0,0 +1,243 @@
+use osrm_openapi_models::models::RouteStep;
+
+use crate::{instructions::utilities::step_refs, osrm::RouteStepExt};
+
+/// Options applied to every SSML announcement.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SsmlOptions {
+    /// Wraps announcements in `<prosody rate="...">`, e.g. "1.08" or "medium".
+    pub prosody_rate: Option<String>,
+}
+
+/// Builds an SSML document from a plain announcement.
+///
+/// Route refs and exit numbers registered with [`SsmlBuilder::address`] are read with
+/// `<say-as interpret-as="address">` ("I 70" as "I seventy", not "I seven zero"), names
+/// registered with [`SsmlBuilder::phoneme`] are read with their pronunciation, and all
+/// other text is XML escaped.
+#[derive(Debug, Clone, Default)]
+pub struct SsmlBuilder {
+    terms: Vec<(String, Markup)>,
+    prosody_rate: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Markup {
+    Address,
+    Phoneme(String),
+}
+
+impl SsmlBuilder {
+    pub fn new(options: &SsmlOptions) -> Self {
+        SsmlBuilder {
+            terms: Vec::new(),
+            prosody_rate: options.prosody_rate.clone(),
+        }
+    }
+
+    /// Collects the refs, exit numbers and pronunciations of a step. Refs are normalized as
+    /// they are announced, e.g. "a 7" as "A 7".
+    pub fn with_step(mut self, step: &RouteStep) -> Self {
+        for r#ref in step_refs(step) {
+            self = self.address(&r#ref);
+        }
+
+        for r#ref in step
+            .signed_destinations()
+            .ok()
+            .flatten()
+            .map(|destinations| destinations.refs)
+            .unwrap_or_default()
+        {
+            self = self.address(&r#ref);
+        }
+
+        for exit in step.exit_numbers().unwrap_or_default() {
+            self = self.address(&exit);
+        }
+
+        if let (Some(name), Some(pronunciation)) = (&step.name, &step.pronunciation) {
+            self = self.phoneme(name, pronunciation);
+        }
+
+        self
+    }
+
+    /// Reads `term` as an address, e.g. a route ref or an exit number.
+    pub fn address(mut self, term: &str) -> Self {
+        self.add_term(term, Markup::Address);
+        self
+    }
+
+    /// Reads `term` with the given IPA pronunciation.
+    pub fn phoneme(mut self, term: &str, ipa: &str) -> Self {
+        self.add_term(term, Markup::Phoneme(ipa.to_string()));
+        self
+    }
+
+    pub fn build(&self, announcement: &str) -> String {
+        let body = self.markup(announcement);
+        let body = match &self.prosody_rate {
+            Some(rate) => format!(r#"<prosody rate="{}">{}</prosody>"#, escape_xml(rate), body),
+            None => body,
+        };
+        format!("<speak>{}</speak>", body)
+    }
+
+    fn add_term(&mut self, term: &str, markup: Markup) {
+        let term = term.trim();
+        if !term.is_empty() && !self.terms.iter().any(|(t, _)| t == term) {
+            self.terms.push((term.to_string(), markup));
+        }
+    }
+
+    /// Escapes the announcement and wraps each registered term, preferring the longest
+    /// term where several overlap ("I 70 Business" over "I 70").
+    fn markup(&self, announcement: &str) -> String {
+        let mut output = String::new();
+        let mut position = 0;
+
+        while position < announcement.len() {
+            let rest = &announcement[position..];
+            let at_word_start = announcement[..position]
+                .chars()
+                .next_back()
+                .is_none_or(|c| !c.is_alphanumeric());
+
+            let matched = self
+                .terms
+                .iter()
+                .filter(|(term, _)| at_word_start && rest.starts_with(term.as_str()))
+                .filter(|(term, _)| {
+                    rest[term.len()..]
+                        .chars()
+                        .next()
+                        .is_none_or(|c| !c.is_alphanumeric())
+                })
+                .max_by_key(|(term, _)| term.len());
+
+            match matched {
+                Some((term, markup)) => {
+                    output.push_str(&wrap(term, markup));
+                    position += term.len();
+                }
+                None => {
+                    let c = rest.chars().next().unwrap();
+                    output.push_str(&escape_xml(&c.to_string()));
+                    position += c.len_utf8();
+                }
+            }
+        }
+
+        output
+    }
+}
+
+fn wrap(term: &str, markup: &Markup) -> String {
+    match markup {
+        Markup::Address => format!(
+            r#"<say-as interpret-as="address">{}</say-as>"#,
+            escape_xml(term)
+        ),
+        Markup::Phoneme(ipa) => format!(
+            r#"<phoneme alphabet="ipa" ph="{}">{}</phoneme>"#,
+            escape_xml(ipa),
+            escape_xml(term)
+        ),
+    }
+}
+
+pub fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            escape_xml(r#"Tom & Jerry's <"Street">"#),
+            "Tom &amp; Jerry&apos;s &lt;&quot;Street&quot;&gt;"
+        );
+    }
+
+    #[test]
+    fn test_build_plain() {
+        let ssml = SsmlBuilder::new(&SsmlOptions::default()).build("Turn left onto A & B Street.");
+        assert_eq!(ssml, "<speak>Turn left onto A &amp; B Street.</speak>");
+    }
+
+    #[test]
+    fn test_build_addresses_prefer_longest() {
+        let ssml = SsmlBuilder::new(&SsmlOptions::default())
+            .address("I 70")
+            .address("I 70 Business")
+            .build("Continue on I 70 Business, then I 70.");
+        assert_eq!(
+            ssml,
+            "<speak>Continue on <say-as interpret-as=\"address\">I 70 Business</say-as>, \
+             then <say-as interpret-as=\"address\">I 70</say-as>.</speak>"
+        );
+    }
+
+    #[test]
+    fn test_build_matches_whole_words() {
+        let ssml = SsmlBuilder::new(&SsmlOptions::default())
+            .address("37")
+            .build("Take exit 37 in 370 meters.");
+        assert_eq!(
+            ssml,
+            "<speak>Take exit <say-as interpret-as=\"address\">37</say-as> in 370 meters.</speak>"
+        );
+    }
+
+    #[test]
+    fn test_build_with_step() {
+        let step = RouteStep {
+            name: Some("Tchoupitoulas Street".to_string()),
+            pronunciation: Some("ˈtʃʌpəˈtuːləs".to_string()),
+            r#ref: Some("I 70 Business; US 6".to_string()),
+            exits: Some(json!("37")),
+            destinations: Some(json!("I 70 Business Loop, US 50: Clifton")),
+            ..Default::default()
+        };
+        let options = SsmlOptions {
+            prosody_rate: Some("1.08".to_string()),
+        };
+
+        let ssml = SsmlBuilder::new(&options)
+            .with_step(&step)
+            .build("Take exit 37 onto US 50 toward Tchoupitoulas Street.");
+        assert_eq!(
+            ssml,
+            "<speak><prosody rate=\"1.08\">Take exit <say-as interpret-as=\"address\">37</say-as> \
+             onto <say-as interpret-as=\"address\">US 50</say-as> toward \
+             <phoneme alphabet=\"ipa\" ph=\"ˈtʃʌpəˈtuːləs\">Tchoupitoulas Street</phoneme>.\
+             </prosody></speak>"
+        );
+    }
+
+    #[test]
+    fn test_build_with_step_normalizes_refs() {
+        let step = RouteStep {
+            r#ref: Some("a 7; us 101".to_string()),
+            ..Default::default()
+        };
+
+        let ssml = SsmlBuilder::new(&SsmlOptions::default())
+            .with_step(&step)
+            .build("Continue on A 7, then US 101.");
+        assert_eq!(
+            ssml,
+            "<speak>Continue on <say-as interpret-as=\"address\">A 7</say-as>, then \
+             <say-as interpret-as=\"address\">US 101</say-as>.</speak>"
+        );
+    }
+}
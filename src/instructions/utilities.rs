@@ -1,44 +1,243 @@
-use osrm_openapi_models::models::{Annotation, RouteStep};
+use osrm_openapi_models::models::{Annotation, ManeuverModifier, ManeuverType, RouteStep};
+use rust_i18n::t;
 
-use crate::geo::utilities::get_coordinate_index;
-use crate::osrm::RouteStepExt;
+use crate::geo::step_geometry::StepGeometry;
+use crate::locale::Locale;
+use crate::osrm::{RouteStepExt, models::ExtendedBannerComponent};
 
-pub(crate) fn step_maneuver_name(step: RouteStep) -> String {
+pub(crate) fn step_maneuver_name(step: RouteStep, locale: Locale) -> String {
     step.r#ref
         .clone()
         .map(|s| normalize_ref(&s))
         .filter(|s| !s.is_empty())
         .or_else(|| step.name.clone().filter(|s| !s.is_empty()))
         .or_else(
-            || match (step.exits.is_none(), step.destinations.is_none()) {
-                (true, false) => step
-                    .destinations_string()
-                    .ok()
-                    .flatten()
-                    .filter(|s| !s.is_empty()),
-                (false, false) => {
-                    let exits = step.exits_string().ok().flatten().unwrap_or_default();
-                    let destinations = step
-                        .destinations_string()
-                        .ok()
-                        .flatten()
-                        .unwrap_or_default();
-                    Some(format!("{} {}", exits, destinations))
-                }
+            || match (step_exit_number(&step), step_destination_names(&step)) {
+                (None, Some(destinations)) => Some(destinations),
+                (Some(exit), Some(destinations)) => Some(
+                    t!(
+                        "Exit %{exit} toward %{destinations}",
+                        locale = locale.as_str(),
+                        exit = exit,
+                        destinations = destinations
+                    )
+                    .to_string(),
+                ),
                 _ => None,
             },
         )
         .unwrap_or_default()
 }
 
+/// A step's maneuver instruction composed from its type, modifier and name, e.g. "Biegen Sie
+/// links ab auf Elberta Avenue." Used where the server's instruction, which is taken to be in
+/// English, can't be.
+pub(crate) fn maneuver_instruction(step: &RouteStep, locale: Locale) -> Option<String> {
+    let maneuver = step.maneuver.as_ref()?;
+    let name = step_maneuver_name(step.clone(), locale);
+    let direction = maneuver.modifier.map(|modifier| {
+        t!(
+            format!("directions.{}", direction_key(modifier)),
+            locale = locale.as_str()
+        )
+        .to_string()
+    });
+
+    let (named, unnamed) = match (maneuver.r#type, maneuver.modifier) {
+        (Some(ManeuverType::Depart), _) => ("Head out on %{name}.", "Head out."),
+        (Some(ManeuverType::Arrive), _) => (
+            "You have arrived at your destination.",
+            "You have arrived at your destination.",
+        ),
+        (Some(ManeuverType::Merge), _) => ("Merge onto %{name}.", "Merge."),
+        (Some(ManeuverType::OnRamp), _) => ("Take the ramp onto %{name}.", "Take the ramp."),
+        (Some(ManeuverType::OffRamp), _) => ("Take the exit onto %{name}.", "Take the exit."),
+        (
+            Some(
+                ManeuverType::Roundabout
+                | ManeuverType::Rotary
+                | ManeuverType::RoundaboutTurn
+                | ManeuverType::ExitRoundabout
+                | ManeuverType::ExitRotary,
+            ),
+            _,
+        ) => (
+            "Enter the roundabout and exit onto %{name}.",
+            "Enter the roundabout.",
+        ),
+        (_, Some(ManeuverModifier::Uturn)) => ("Make a U-turn onto %{name}.", "Make a U-turn."),
+        (_, Some(ManeuverModifier::Straight) | None) => ("Continue onto %{name}.", "Continue."),
+        (Some(ManeuverType::Fork), _) => ("Keep %{direction} onto %{name}.", "Keep %{direction}."),
+        _ => ("Turn %{direction} onto %{name}.", "Turn %{direction}."),
+    };
+
+    let template = if name.is_empty() { unnamed } else { named };
+    Some(
+        t!(
+            template,
+            locale = locale.as_str(),
+            name = name,
+            direction = direction.unwrap_or_default()
+        )
+        .to_string(),
+    )
+}
+
+/// The key of a modifier's direction in `locales/locales.yml`.
+fn direction_key(modifier: ManeuverModifier) -> &'static str {
+    match modifier {
+        ManeuverModifier::Uturn => "uturn",
+        ManeuverModifier::SharpRight => "sharp_right",
+        ManeuverModifier::Right => "right",
+        ManeuverModifier::SlightRight => "slight_right",
+        ManeuverModifier::Straight => "straight",
+        ManeuverModifier::SlightLeft => "slight_left",
+        ManeuverModifier::Left => "left",
+        ManeuverModifier::SharpLeft => "sharp_left",
+    }
+}
+
+/// Splits a step's label into typed banner components: the exit number, route shields
+/// (`icon`) separated by `delimiter` components, or otherwise the name as `text`.
+pub(crate) fn step_banner_components(
+    step: &RouteStep,
+    locale: Locale,
+) -> Vec<ExtendedBannerComponent> {
+    let mut components = Vec::new();
+
+    if let Some(exit) = step_exit_number(step) {
+        components.push(ExtendedBannerComponent::new(
+            "exit",
+            t!("Exit", locale = locale.as_str()).to_string(),
+        ));
+        components.push(ExtendedBannerComponent::new("exit-number", exit));
+    }
+
+    let refs = step_refs(step);
+    if step_banner_shows_destinations(step) {
+        let text = step_destination_names(step).unwrap_or_default();
+        components.push(ExtendedBannerComponent::new("text", text));
+    } else if refs.is_empty() {
+        let text = step.name.clone().unwrap_or_default();
+        components.push(ExtendedBannerComponent::new("text", text));
+    } else {
+        for (index, r#ref) in refs.into_iter().enumerate() {
+            if index > 0 {
+                components.push(ExtendedBannerComponent::new("delimiter", "/".to_string()));
+            }
+            components.push(ExtendedBannerComponent::new("icon", r#ref));
+        }
+    }
+
+    components
+}
+
+/// Whether a step's banner components name it by its signed destinations, as it has neither
+/// refs nor a name.
+pub(crate) fn step_banner_shows_destinations(step: &RouteStep) -> bool {
+    step_refs(step).is_empty() && step.name.as_deref().is_none_or(str::is_empty)
+}
+
+/// The signed destinations a step's banner components don't already name it by. These are its
+/// destination names, or its destination refs when no names are signed, without those equal to
+/// the step's refs or name.
+pub(crate) fn step_other_destinations(step: &RouteStep) -> Vec<String> {
+    if step_banner_shows_destinations(step) {
+        return Vec::new();
+    }
+
+    let Some(destinations) = step.signed_destinations().ok().flatten() else {
+        return Vec::new();
+    };
+    let refs = step_refs(step);
+    let destinations = if destinations.names.is_empty() {
+        destinations.refs
+    } else {
+        destinations.names
+    };
+
+    destinations
+        .into_iter()
+        .filter(|destination| {
+            !refs.contains(&normalize_ref(destination))
+                && step.name.as_deref() != Some(destination.as_str())
+        })
+        .collect()
+}
+
+/// The banner text spelled out by its components, e.g. "Exit 37 I 70 / US 6".
+pub(crate) fn banner_components_text(components: &[ExtendedBannerComponent]) -> String {
+    components
+        .iter()
+        .filter_map(|component| component.text.as_deref())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The step's route refs. OSRM separates multiple refs with ";", e.g. "I 70; US 6".
+pub(crate) fn step_refs(step: &RouteStep) -> Vec<String> {
+    step.r#ref
+        .as_deref()
+        .unwrap_or_default()
+        .split(';')
+        .map(|r#ref| normalize_ref(r#ref.trim()))
+        .filter(|r#ref| !r#ref.is_empty())
+        .collect()
+}
+
+/// The first of the step's exit numbers.
+pub(crate) fn step_exit_number(step: &RouteStep) -> Option<String> {
+    step.exit_numbers().ok()?.into_iter().next()
+}
+
+/// The signed destination names of a step, or its destination refs when no names are signed.
+pub(crate) fn step_destination_names(step: &RouteStep) -> Option<String> {
+    step.signed_destinations()
+        .ok()
+        .flatten()
+        .map(|destinations| {
+            if destinations.names.is_empty() {
+                destinations.refs.join(", ")
+            } else {
+                destinations.names.join(", ")
+            }
+        })
+}
+
+/// Joins items for speech, e.g. "Clifton, Grand Junction and Delta".
+pub(crate) fn spoken_list(items: &[String], locale: Locale) -> Option<String> {
+    match items {
+        [] => None,
+        [item] => Some(item.clone()),
+        [items @ .., last] => Some(
+            t!(
+                "%{items} and %{last}",
+                locale = locale.as_str(),
+                items = items.join(", "),
+                last = last
+            )
+            .to_string(),
+        ),
+    }
+}
+
+/// The annotated speed of the segment `distance` meters along the step.
+///
+/// Annotations have one entry per segment, not per coordinate, so the segment the distance falls
+/// on is looked up rather than the nearest coordinate. Near the end of a step the nearest
+/// coordinate is its last one, which has no entry.
 pub(crate) fn speed_at_distance(
-    geometry: String,
-    annotations: Annotation,
+    geometry: &StepGeometry,
+    annotations: &Annotation,
     distance: f64,
-    polyline_precision: u32,
 ) -> Option<f64> {
-    let index = get_coordinate_index(geometry.as_str(), polyline_precision, distance)?;
-    annotations.speed?.get(index).copied()
+    let position = geometry.position_at_distance(distance)?;
+    annotations
+        .speed
+        .as_ref()?
+        .get(position.segment_index)
+        .copied()
 }
 
 fn normalize_ref(s: &str) -> String {
@@ -51,8 +250,80 @@ fn normalize_ref(s: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::geo::utilities::get_coordinates_from_geometry;
+    use osrm_openapi_models::models::StepManeuver;
     use serde_json::json;
 
+    #[test]
+    fn test_speed_at_distance() {
+        // Two segments, about 51 m and 45 m long, with one speed each.
+        let geometry =
+            StepGeometry::new(get_coordinates_from_geometry("epxkF|`miVfAfA~@~@", 5).unwrap());
+        let annotations = Annotation {
+            speed: Some(vec![10.0, 20.0]),
+            ..Default::default()
+        };
+
+        assert_eq!(speed_at_distance(&geometry, &annotations, 0.0), Some(10.0));
+        // Past the middle of the first segment, whose nearest coordinate starts the second.
+        assert_eq!(speed_at_distance(&geometry, &annotations, 40.0), Some(10.0));
+        assert_eq!(speed_at_distance(&geometry, &annotations, 60.0), Some(20.0));
+        // Near the end, whose nearest coordinate is the last one and has no speed.
+        assert_eq!(speed_at_distance(&geometry, &annotations, 90.0), Some(20.0));
+        assert_eq!(
+            speed_at_distance(&geometry, &annotations, 1000.0),
+            Some(20.0)
+        );
+    }
+
+    #[test]
+    fn test_maneuver_instruction() {
+        let step = |r#type, modifier, name: &str| RouteStep {
+            name: Some(name.to_string()),
+            maneuver: Some(Box::new(StepManeuver {
+                r#type: Some(r#type),
+                modifier,
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+
+        let turn = step(
+            ManeuverType::Turn,
+            Some(ManeuverModifier::SlightLeft),
+            "Main",
+        );
+        assert_eq!(
+            maneuver_instruction(&turn, Locale::De).unwrap(),
+            "Biegen Sie leicht links ab auf Main."
+        );
+        assert_eq!(
+            maneuver_instruction(&turn, Locale::Es).unwrap(),
+            "Gire ligeramente a la izquierda hacia Main."
+        );
+
+        let fork = step(ManeuverType::Fork, Some(ManeuverModifier::Right), "");
+        assert_eq!(
+            maneuver_instruction(&fork, Locale::Fr).unwrap(),
+            "Restez à droite."
+        );
+
+        let new_name = step(
+            ManeuverType::NewName,
+            Some(ManeuverModifier::Straight),
+            "Main",
+        );
+        assert_eq!(
+            maneuver_instruction(&new_name, Locale::En).unwrap(),
+            "Continue onto Main."
+        );
+
+        assert_eq!(
+            maneuver_instruction(&RouteStep::default(), Locale::De),
+            None
+        );
+    }
+
     #[test]
     fn test_step_maneuver_label_with_name() {
         let step = RouteStep {
@@ -63,7 +334,7 @@ mod tests {
             ..Default::default()
         };
 
-        assert_eq!(step_maneuver_name(step), "Main Street");
+        assert_eq!(step_maneuver_name(step, Locale::En), "Main Street");
     }
 
     #[test]
@@ -76,7 +347,7 @@ mod tests {
             ..Default::default()
         };
 
-        assert_eq!(step_maneuver_name(step), "I-95");
+        assert_eq!(step_maneuver_name(step, Locale::En), "I-95");
     }
 
     #[test]
@@ -89,7 +360,7 @@ mod tests {
             ..Default::default()
         };
 
-        assert_eq!(step_maneuver_name(step), "I-95");
+        assert_eq!(step_maneuver_name(step, Locale::En), "I-95");
     }
 
     #[test]
@@ -113,7 +384,7 @@ mod tests {
             ..Default::default()
         };
 
-        assert_eq!(step_maneuver_name(step), "Boston");
+        assert_eq!(step_maneuver_name(step, Locale::En), "Boston");
     }
 
     #[test]
@@ -121,12 +392,108 @@ mod tests {
         let step = RouteStep {
             name: None,
             r#ref: None,
-            exits: Some(json!("Exit 23")),
+            exits: Some(json!("23")),
             destinations: Some(json!("New York")),
             ..Default::default()
         };
 
-        assert_eq!(step_maneuver_name(step), "Exit 23 New York");
+        assert_eq!(
+            step_maneuver_name(step, Locale::En),
+            "Exit 23 toward New York"
+        );
+    }
+
+    #[test]
+    fn test_step_banner_components_refs() {
+        let step = RouteStep {
+            name: Some("Interstate 70 Business Loop".to_string()),
+            r#ref: Some("i 70 Business; US 6".to_string()),
+            exits: Some(json!("37")),
+            ..Default::default()
+        };
+
+        let components = step_banner_components(&step, Locale::En);
+        let types = components
+            .iter()
+            .map(|c| c.r#type.clone().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            types,
+            vec!["exit", "exit-number", "icon", "delimiter", "icon"]
+        );
+        assert_eq!(
+            banner_components_text(&components),
+            "Exit 37 I 70 Business / US 6"
+        );
+    }
+
+    #[test]
+    fn test_step_banner_components_name() {
+        let step = RouteStep {
+            name: Some("Main Street".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            step_banner_components(&step, Locale::En),
+            vec![ExtendedBannerComponent::new(
+                "text",
+                "Main Street".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_step_banner_components_destinations() {
+        let step = RouteStep {
+            name: Some(String::new()),
+            destinations: Some(json!("Indiana Street, Denver West")),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            banner_components_text(&step_banner_components(&step, Locale::En)),
+            "Indiana Street, Denver West"
+        );
+    }
+
+    #[test]
+    fn test_step_destination_names() {
+        let step = RouteStep {
+            destinations: Some(json!("I 70 Business Loop, US 6: Clifton, Grand Junction")),
+            ..Default::default()
+        };
+        assert_eq!(
+            step_destination_names(&step),
+            Some("Clifton, Grand Junction".to_string())
+        );
+
+        let step = RouteStep {
+            destinations: Some(json!("Denver")),
+            ..Default::default()
+        };
+        assert_eq!(step_destination_names(&step), Some("Denver".to_string()));
+
+        assert_eq!(step_destination_names(&RouteStep::default()), None);
+    }
+
+    #[test]
+    fn test_spoken_list() {
+        let items = ["Clifton", "Grand Junction", "Delta"].map(String::from);
+        assert_eq!(
+            spoken_list(&items[..1], Locale::En),
+            Some("Clifton".to_string())
+        );
+        assert_eq!(
+            spoken_list(&items, Locale::En),
+            Some("Clifton, Grand Junction and Delta".to_string())
+        );
+        assert_eq!(
+            spoken_list(&items[..2], Locale::De),
+            Some("Clifton und Grand Junction".to_string())
+        );
+        assert_eq!(spoken_list(&[], Locale::En), None);
     }
 
     #[test]
@@ -139,7 +506,7 @@ mod tests {
             ..Default::default()
         };
 
-        assert_eq!(step_maneuver_name(step), "");
+        assert_eq!(step_maneuver_name(step, Locale::En), "");
     }
 
     #[test]
@@ -153,6 +520,6 @@ mod tests {
             ..Default::default()
         };
 
-        assert_eq!(step_maneuver_name(step), "I-95");
+        assert_eq!(step_maneuver_name(step, Locale::En), "I-95");
     }
 }
@@ -1,8 +1,9 @@
 use osrm_openapi_models::models::ManeuverType;
 
 use crate::{
-    distance::{Distance, Unit},
-    osrm::{RouteStepBundle, RouteStepExt},
+    distance::{Distance, Unit, UnitSystem},
+    geo::step_geometry::StepGeometry,
+    osrm::RouteStepBundle,
 };
 
 use super::{speed_class::SpeedClass, utilities::speed_at_distance};
@@ -57,25 +58,39 @@ pub enum AnnounceAt {
     ManeuverAndThen(Distance),
 }
 
+/// Whether an announcement is made for a step, and why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnouncementDecision {
+    pub announcement: AnnouncementType,
+    /// Where the announcement is made, or `None` when it is not made.
+    pub announce_at: Option<AnnounceAt>,
+    pub reason: String,
+}
+
 pub struct VoiceAnnouncements {
     current: RouteStepBundle,
     next: Option<RouteStepBundle>,
-    metric: bool,
-    polyline_precision: u32,
+    unit_system: UnitSystem,
+    /// The current step's geometry, when it can be decoded.
+    geometry: Option<StepGeometry>,
 }
 
 impl VoiceAnnouncements {
     pub(crate) fn new(
         current: RouteStepBundle,
         next: Option<RouteStepBundle>,
-        metric: bool,
+        unit_system: UnitSystem,
         polyline_precision: u32,
     ) -> Self {
+        let geometry = StepGeometry::from_step(&current.step, polyline_precision)
+            .ok()
+            .flatten();
+
         VoiceAnnouncements {
             current,
             next,
-            metric,
-            polyline_precision,
+            unit_system,
+            geometry,
         }
     }
 
@@ -96,6 +111,30 @@ impl VoiceAnnouncements {
             .collect()
     }
 
+    /// Explains the decision for every type of announcement, including those not made.
+    pub fn explain(&self) -> Vec<AnnouncementDecision> {
+        [
+            AnnouncementType::Depart,
+            AnnouncementType::Continue,
+            AnnouncementType::PreApproach,
+            AnnouncementType::Approach,
+            AnnouncementType::Maneuver,
+            AnnouncementType::ManeuverAndThen,
+        ]
+        .into_iter()
+        .map(|announcement| {
+            let announce_at = self
+                .create_announcement(announcement)
+                .filter(|a| self.should_announce(*a));
+            AnnouncementDecision {
+                announcement,
+                announce_at,
+                reason: self.reason(announcement, announce_at.is_some()),
+            }
+        })
+        .collect()
+    }
+
     /// Calculates the distance at which a specific announcement should be made.
     /// This does not consider whether the announcement should be made at all.
     fn create_announcement(&self, announcement: AnnouncementType) -> Option<AnnounceAt> {
@@ -176,11 +215,6 @@ impl VoiceAnnouncements {
     /// Any step can say every announcement. This just allows us to filter only those that are
     /// needed for the current step.
     fn should_announce(&self, announce_at: AnnounceAt) -> bool {
-        let make_distance = if self.metric {
-            Distance::from_kilometers
-        } else {
-            Distance::from_miles
-        };
         let length = Distance::from_meters(self.current.step.distance.unwrap_or(0.0));
         let next_length = self
             .next
@@ -193,26 +227,84 @@ impl VoiceAnnouncements {
         );
 
         let needs_then =
-            next_length.is_some_and(|length| length < make_distance(THEN_MINIMUM_DISTANCE));
+            next_length.is_some_and(|length| length < self.distance(THEN_MINIMUM_DISTANCE));
 
         match announce_at {
             AnnounceAt::Depart(..) => is_depart,
-            AnnounceAt::Continue(..) => length >= make_distance(CONTINUE_MINIMUM_DISTANCE),
-            AnnounceAt::PreApproach(..) => length >= make_distance(PRE_APPROACH_MINIMUM_DISTANCE),
-            AnnounceAt::Approach(..) => length >= make_distance(APPROACH_MINIMUM_DISTANCE),
+            AnnounceAt::Continue(..) => length >= self.distance(CONTINUE_MINIMUM_DISTANCE),
+            AnnounceAt::PreApproach(..) => length >= self.distance(PRE_APPROACH_MINIMUM_DISTANCE),
+            AnnounceAt::Approach(..) => length >= self.distance(APPROACH_MINIMUM_DISTANCE),
             AnnounceAt::Maneuver(..) => !needs_then,
             AnnounceAt::ManeuverAndThen(..) => needs_then,
         }
     }
 
-    fn distance(&self, constant_dist: f64) -> Distance {
-        if self.metric {
-            Distance::from_kilometers(constant_dist)
-        } else {
-            Distance::from_miles(constant_dist)
+    /// Describes the thresholds and speed behind an announcement's decision.
+    fn reason(&self, announcement: AnnouncementType, announced: bool) -> String {
+        let step_distance = Distance::from_meters(self.current.step.distance.unwrap_or(0.0))
+            .to(self.unit_system.long_unit());
+        let speed_class = self
+            .speed_class(self.pct_of_distance(step_distance, 0.95))
+            .map_or("unknown", |speed_class| speed_class.as_str());
+        let then_distance = self.distance(THEN_MINIMUM_DISTANCE);
+
+        match (announcement, announced) {
+            (AnnouncementType::Depart, true) => "the step departs".to_string(),
+            (AnnouncementType::Depart, false) => "the step doesn't depart".to_string(),
+            (AnnouncementType::Continue, announced) => format!(
+                "the step is {} long, {} {}",
+                step_distance,
+                if announced {
+                    "at least"
+                } else {
+                    "shorter than"
+                },
+                self.distance(CONTINUE_MINIMUM_DISTANCE)
+            ),
+            (AnnouncementType::PreApproach, false) => format!(
+                "the step is {} long, shorter than {}",
+                step_distance,
+                self.distance(PRE_APPROACH_MINIMUM_DISTANCE)
+            ),
+            (AnnouncementType::PreApproach, true) => format!(
+                "the step is {} long, {} {}",
+                step_distance,
+                if step_distance > self.distance(PRE_APPROACH_LONG_THRESHOLD) {
+                    "longer than"
+                } else {
+                    "at most"
+                },
+                self.distance(PRE_APPROACH_LONG_THRESHOLD)
+            ),
+            (AnnouncementType::Approach, false) => format!(
+                "the step is {} long, shorter than {}",
+                step_distance,
+                self.distance(APPROACH_MINIMUM_DISTANCE)
+            ),
+            (AnnouncementType::Approach, true) => format!(
+                "the step is {} long and the speed before the maneuver is {}",
+                step_distance, speed_class
+            ),
+            (AnnouncementType::Maneuver, true) => {
+                format!("the speed before the maneuver is {}", speed_class)
+            }
+            (AnnouncementType::Maneuver, false) => format!(
+                "the next step is shorter than {}, so the maneuver is announced with it",
+                then_distance
+            ),
+            (AnnouncementType::ManeuverAndThen, true) => {
+                format!("the next step is shorter than {}", then_distance)
+            }
+            (AnnouncementType::ManeuverAndThen, false) => {
+                format!("the next step is at least {} long", then_distance)
+            }
         }
     }
 
+    fn distance(&self, constant_dist: f64) -> Distance {
+        Distance::new(constant_dist, self.unit_system.long_unit())
+    }
+
     fn pct_of_distance(&self, distance: Distance, pct: f64) -> Distance {
         let new_distance = distance.value() * pct;
         Distance::new(new_distance, distance.unit())
@@ -224,23 +316,10 @@ impl VoiceAnnouncements {
         // future.
         let meters = distance.to(Unit::Meters).value();
 
-        self.current
-            .step
-            .geometry_string()
-            .ok()
-            .flatten()
-            .and_then(|geometry| {
-                self.current.annotation.as_ref().map(|annotations| {
-                    let mps = speed_at_distance(
-                        geometry,
-                        annotations.as_ref().clone(),
-                        meters,
-                        self.polyline_precision,
-                    );
-                    SpeedClass::from_meters_per_second(mps)
-                })
-            })
-            .flatten()
+        let geometry = self.geometry.as_ref()?;
+        let annotations = self.current.annotation.as_ref()?;
+        let mps = speed_at_distance(geometry, annotations, meters);
+        SpeedClass::from_meters_per_second(mps)
     }
 
     fn get_maneuver_distance(&self, step_distance: Distance) -> Distance {
@@ -263,12 +342,7 @@ mod tests {
         current: RouteStepBundle,
         next: Option<RouteStepBundle>,
     ) -> Vec<AnnounceAt> {
-        let voice_announcements = VoiceAnnouncements {
-            current,
-            next,
-            metric: true,
-            polyline_precision: 5,
-        };
+        let voice_announcements = VoiceAnnouncements::new(current, next, UnitSystem::Metric, 5);
 
         voice_announcements.build()
     }
@@ -297,6 +371,15 @@ mod tests {
         assert_debug_snapshot!(announcements);
     }
 
+    #[test]
+    fn test_explain_long_step() {
+        let (current, next, _) =
+            load_route_steps("./fixtures/valhalla-short.json", 0, 2, POLYLINE_PRECISION);
+        let voice_announcements =
+            VoiceAnnouncements::new(current, next, UnitSystem::Metric, POLYLINE_PRECISION);
+        assert_debug_snapshot!(voice_announcements.explain());
+    }
+
     #[test]
     fn test_and_then_step() {
         let (current, next, _) =
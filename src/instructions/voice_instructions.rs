@@ -1,21 +1,43 @@
+use round::round;
 use rust_i18n::t;
 
-use osrm_openapi_models::models::VoiceInstruction;
+use osrm_openapi_models::models::{ManeuverType, RouteStep, VoiceInstruction};
 
 use crate::{
-    distance::{Distance, Unit, spoken_distance::SpokenDistance},
-    instructions::voice_announcements::VoiceAnnouncements,
-    osrm::{RouteStepBundle, StepManeuverExt},
+    distance::{Distance, Unit, UnitSystem, spoken_distance::SpokenDistance},
+    instructions::{
+        arrival::Arrival,
+        ssml::{SsmlBuilder, SsmlOptions},
+        voice_announcements::{AnnouncementDecision, VoiceAnnouncements},
+    },
+    locale::Locale,
+    osrm::{RouteStepBundle, RouteStepExt, StepManeuverExt},
+    speed_limit::{SpeedLimit, SpeedLimitTimeline, SpeedUnit},
 };
 
-use super::{utilities::step_maneuver_name, voice_announcements::AnnounceAt};
+use super::{
+    utilities::{maneuver_instruction, spoken_list, step_exit_number, step_maneuver_name},
+    voice_announcements::AnnounceAt,
+};
+
+/// Off ramps are announced toward at most this many of their signed destinations.
+const MAXIMUM_SPOKEN_DESTINATIONS: usize = 2;
+
+/// Speed limit announcements are dropped within this distance of another announcement
+/// (km or mi).
+const SPEED_LIMIT_CLEARANCE: f64 = 0.1;
 
 pub struct VoiceInstructionFactory {
     current: RouteStepBundle,
     next: RouteStepBundle,
     step_after_next: Option<RouteStepBundle>,
-    metric: bool,
+    unit_system: UnitSystem,
+    polyline_precision: u32,
     announcements: VoiceAnnouncements,
+    arrival: Option<Arrival>,
+    ssml_options: SsmlOptions,
+    locale: Locale,
+    speed_limit_announcements: bool,
 }
 
 impl VoiceInstructionFactory {
@@ -23,110 +45,239 @@ impl VoiceInstructionFactory {
         current: RouteStepBundle,
         next: RouteStepBundle,
         step_after_next: Option<RouteStepBundle>,
-        metric: bool,
+        unit_system: UnitSystem,
         polyline_precision: u32,
     ) -> Self {
         VoiceInstructionFactory {
             current: current.clone(),
             next: next.clone(),
             step_after_next: step_after_next.clone(),
-            metric,
+            unit_system,
+            polyline_precision,
             // TODO: This may need to consider step_after_next
-            announcements: VoiceAnnouncements::new(current, Some(next), metric, polyline_precision),
+            announcements: VoiceAnnouncements::new(
+                current,
+                Some(next),
+                unit_system,
+                polyline_precision,
+            ),
+            arrival: None,
+            ssml_options: SsmlOptions::default(),
+            locale: Locale::default(),
+            speed_limit_announcements: false,
         }
     }
 
-    pub fn build(&self) -> Vec<VoiceInstruction> {
+    pub fn with_ssml_options(mut self, ssml_options: SsmlOptions) -> Self {
+        self.ssml_options = ssml_options;
+        self
+    }
+
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Sets where the step's leg ends. When the next step arrives there, the arrival
+    /// announcements name the stop or destination instead of repeating the server's instruction.
+    pub fn with_arrival(mut self, arrival: Option<Arrival>) -> Self {
+        self.arrival = arrival;
+        self
+    }
+
+    /// Announces where the speed limit changes along the step, e.g. "Speed limit 45.", in
+    /// between the step's other announcements.
+    pub fn with_speed_limit_announcements(mut self, speed_limit_announcements: bool) -> Self {
+        self.speed_limit_announcements = speed_limit_announcements;
+        self
+    }
+
+    /// Explains every type of announcement, paired with the instruction made for it.
+    pub fn explain(&self) -> Vec<(AnnouncementDecision, Option<VoiceInstruction>)> {
         self.announcements
+            .explain()
+            .into_iter()
+            .map(|decision| {
+                let instruction = decision
+                    .announce_at
+                    .and_then(|announce_at| self.generate(announce_at));
+                (decision, instruction)
+            })
+            .collect()
+    }
+
+    pub fn build(&self) -> Vec<VoiceInstruction> {
+        let mut instructions = self
+            .announcements
             .build()
             .iter()
             .flat_map(|announcement| self.generate(*announcement))
-            .collect()
+            .collect::<Vec<_>>();
+
+        if self.speed_limit_announcements {
+            self.merge_speed_limits(&mut instructions);
+        }
+
+        instructions
+    }
+
+    /// Inserts an announcement where the speed limit changes along the step, keeping
+    /// `instructions` ordered by their distance before the maneuver. A change too close to
+    /// another announcement is not announced.
+    fn merge_speed_limits(&self, instructions: &mut Vec<VoiceInstruction>) {
+        let Some(timeline) = SpeedLimitTimeline::for_step(&self.current, self.polyline_precision)
+            .ok()
+            .flatten()
+        else {
+            return;
+        };
+
+        let step_distance = self.current.step.distance.unwrap_or(0.0);
+        let clearance = self.meters(Distance::new(
+            SPEED_LIMIT_CLEARANCE,
+            self.unit_system.long_unit(),
+        ));
+
+        // Unknown stretches don't end a limit, so a limit resuming after one is not repeated.
+        let mut current_limit = None;
+        for span in timeline.spans() {
+            if span.limit == SpeedLimit::Unknown {
+                continue;
+            }
+            let previous_limit = current_limit.replace(span.limit);
+
+            // The limit at the start of the step is the one the maneuver onto it leads into.
+            if span.start == 0.0 || previous_limit == Some(span.limit) {
+                continue;
+            }
+
+            let distance_along_geometry = round(step_distance - span.start, 2).max(0.0);
+            let clashes = instructions.iter().any(|instruction| {
+                (instruction.distance_along_geometry - distance_along_geometry).abs() < clearance
+            });
+            if clashes {
+                continue;
+            }
+
+            if let Some(announcement) = self.speed_limit_announcement(span.limit) {
+                let index = instructions.partition_point(|instruction| {
+                    instruction.distance_along_geometry > distance_along_geometry
+                });
+                instructions.insert(
+                    index,
+                    VoiceInstruction {
+                        distance_along_geometry,
+                        ssml_announcement: self.ssml_announcement(&announcement),
+                        announcement,
+                    },
+                );
+            }
+        }
+    }
+
+    fn speed_limit_announcement(&self, limit: SpeedLimit) -> Option<String> {
+        let locale = self.locale.as_str();
+        match limit {
+            SpeedLimit::Unlimited => Some(t!("No speed limit.", locale = locale).to_string()),
+            _ => limit
+                .speed_in(SpeedUnit::for_unit_system(self.unit_system))
+                .map(|speed| {
+                    t!("Speed limit %{speed}.", locale = locale, speed = speed).to_string()
+                }),
+        }
     }
 
     fn generate(&self, announce_at: AnnounceAt) -> Option<VoiceInstruction> {
-        self.announcement(announce_at)
-            .map(|announcement| match announce_at {
+        self.announcement(announce_at).map(|announcement| {
+            let ssml_announcement = self.ssml_announcement(&announcement);
+            match announce_at {
                 AnnounceAt::Depart(d) => VoiceInstruction {
                     distance_along_geometry: self.meters(d),
                     announcement,
-                    ssml_announcement: self.ssml_announcement(),
+                    ssml_announcement,
                 },
                 AnnounceAt::Continue(d) => VoiceInstruction {
                     distance_along_geometry: self.meters(d),
                     announcement,
-                    ssml_announcement: self.ssml_announcement(),
+                    ssml_announcement,
                 },
                 AnnounceAt::PreApproach(d) => VoiceInstruction {
                     distance_along_geometry: self.meters(d),
                     announcement,
-                    ssml_announcement: self.ssml_announcement(),
+                    ssml_announcement,
                 },
                 AnnounceAt::Approach(d) => VoiceInstruction {
                     distance_along_geometry: self.meters(d),
                     announcement,
-                    ssml_announcement: self.ssml_announcement(),
+                    ssml_announcement,
                 },
                 AnnounceAt::Maneuver(d) => VoiceInstruction {
                     distance_along_geometry: self.meters(d),
                     announcement,
-                    ssml_announcement: self.ssml_announcement(),
+                    ssml_announcement,
                 },
                 AnnounceAt::ManeuverAndThen(d) => VoiceInstruction {
                     distance_along_geometry: self.meters(d),
                     announcement,
-                    ssml_announcement: self.ssml_announcement(),
+                    ssml_announcement,
                 },
-            })
+            }
+        })
     }
 
     fn announcement(&self, announce_at: AnnounceAt) -> Option<String> {
-        let current_instruction = self
-            .current
-            .step
-            .maneuver
-            .as_ref()
-            .and_then(|m| m.instruction_string().ok().flatten());
-
-        self.next
-            .step
-            .maneuver
-            .as_ref()
-            .and_then(|m| m.instruction_string().ok().flatten())
+        let current_instruction = self.step_instruction(&self.current.step);
+        let next_instruction = self.step_instruction(&self.next.step);
+
+        // Arriving at a stop or named destination replaces the server's generic arrival instruction.
+        let (instruction, upcoming_instruction) = match self.next_arrival() {
+            Some(arrival) => (
+                arrival.arrived(self.locale).or(next_instruction.clone()),
+                arrival.arriving(self.locale).or(next_instruction),
+            ),
+            None => (next_instruction.clone(), next_instruction),
+        };
+
+        instruction
+            .zip(upcoming_instruction)
             // Join the next step's instruction with the current step's street name (for continue).
-            .map(|instruction| (instruction, step_maneuver_name(self.current.step.clone())))
-            .map(|(instruction, name)| {
+            .map(|(instruction, upcoming)| {
+                let name = step_maneuver_name(self.current.step.clone(), self.locale);
+                let locale = self.locale.as_str();
                 sanitize_for_voice(match announce_at {
                     AnnounceAt::Depart(..) => {
-                        current_instruction.unwrap_or(t!("depart").to_string())
+                        current_instruction.unwrap_or(t!("depart", locale = locale).to_string())
                     }
                     AnnounceAt::Continue(d) => t!(
                         "Continue on %{name} for %{distance}.",
+                        locale = locale,
                         name = name,
                         distance = self.spoken_distance(d)
                     )
                     .to_string(),
                     AnnounceAt::PreApproach(d) => t!(
                         "In %{distance}, %{instruction}",
+                        locale = locale,
                         distance = self.spoken_distance(d),
-                        instruction = lowercase_first(&instruction)
+                        instruction = lowercase_first(&upcoming)
                     )
                     .to_string(),
                     AnnounceAt::Approach(d) => t!(
                         "In %{distance}, %{instruction}",
+                        locale = locale,
                         distance = self.spoken_distance(d),
-                        instruction = lowercase_first(&instruction)
+                        instruction = lowercase_first(&upcoming)
                     )
                     .to_string(),
                     AnnounceAt::Maneuver(..) => instruction,
                     AnnounceAt::ManeuverAndThen(..) => self
                         .step_after_next
                         .as_ref()
-                        .and_then(|b| b.step.maneuver.as_ref())
-                        .and_then(|m| m.instruction_string().ok().flatten())
+                        .and_then(|b| self.step_instruction(&b.step))
                         .map(|n| {
                             t!(
                                 "%{instruction} Then %{next}",
+                                locale = locale,
                                 instruction = instruction,
                                 next = n
                             )
@@ -137,9 +288,43 @@ impl VoiceInstructionFactory {
             })
     }
 
-    fn ssml_announcement(&self) -> Option<String> {
-        // TODO: SSML Integration?
-        None
+    /// The instruction for a step: an off ramp's exit and destinations, otherwise the server's.
+    ///
+    /// The server's instruction is taken to be in English, so other locales compose the
+    /// instruction from the maneuver instead.
+    fn step_instruction(&self, step: &RouteStep) -> Option<String> {
+        exit_instruction(step, self.locale).or_else(|| match self.locale {
+            Locale::En => step
+                .maneuver
+                .as_ref()
+                .and_then(|m| m.instruction_string().ok().flatten())
+                .or_else(|| maneuver_instruction(step, self.locale)),
+            _ => maneuver_instruction(step, self.locale),
+        })
+    }
+
+    /// The arrival reached at the end of the next step, if the next step arrives.
+    fn next_arrival(&self) -> Option<&Arrival> {
+        let arrives = matches!(
+            self.next.step.maneuver.as_ref().and_then(|m| m.r#type),
+            Some(ManeuverType::Arrive)
+        );
+        self.arrival.as_ref().filter(|_| arrives)
+    }
+
+    fn ssml_announcement(&self, announcement: &str) -> Option<String> {
+        let builder = [
+            Some(&self.current),
+            Some(&self.next),
+            self.step_after_next.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .fold(SsmlBuilder::new(&self.ssml_options), |builder, bundle| {
+            builder.with_step(&bundle.step)
+        });
+
+        Some(builder.build(announcement))
     }
 
     fn meters(&self, distance: Distance) -> f64 {
@@ -147,15 +332,60 @@ impl VoiceInstructionFactory {
     }
 
     fn spoken_distance(&self, distance: Distance) -> String {
-        let converted = if self.metric {
-            distance.to(Unit::Kilometers)
-        } else {
-            distance.to(Unit::Miles)
-        };
-        SpokenDistance::from_distance(converted).spoken()
+        SpokenDistance::from_meters(self.meters(distance), self.unit_system)
+            .with_locale(self.locale)
+            .spoken()
     }
 }
 
+/// Announces an off ramp by its exit number and first signed destinations, e.g. "Take exit 37
+/// toward Clifton and Grand Junction."
+fn exit_instruction(step: &RouteStep, locale: Locale) -> Option<String> {
+    let off_ramp = matches!(
+        step.maneuver.as_ref().and_then(|m| m.r#type),
+        Some(ManeuverType::OffRamp)
+    );
+    if !off_ramp {
+        return None;
+    }
+
+    let destinations = step
+        .signed_destinations()
+        .ok()
+        .flatten()
+        .map(|destinations| {
+            if destinations.names.is_empty() {
+                destinations.refs
+            } else {
+                destinations.names
+            }
+        })
+        .unwrap_or_default();
+    let destinations = spoken_list(
+        &destinations[..destinations.len().min(MAXIMUM_SPOKEN_DESTINATIONS)],
+        locale,
+    );
+
+    let locale = locale.as_str();
+    let instruction = match (step_exit_number(step), destinations) {
+        (Some(exit), Some(destinations)) => t!(
+            "Take exit %{exit} toward %{destinations}.",
+            locale = locale,
+            exit = exit,
+            destinations = destinations
+        ),
+        (Some(exit), None) => t!("Take exit %{exit}.", locale = locale, exit = exit),
+        (None, Some(destinations)) => t!(
+            "Take the ramp toward %{destinations}.",
+            locale = locale,
+            destinations = destinations
+        ),
+        (None, None) => return None,
+    };
+
+    Some(instruction.to_string())
+}
+
 fn sanitize_for_voice(s: String) -> String {
     s.replace('/', ", ")
 }
@@ -179,13 +409,13 @@ mod tests {
         current: RouteStepBundle,
         next: RouteStepBundle,
         step_after_next: Option<RouteStepBundle>,
-        metric: bool,
+        unit_system: UnitSystem,
     ) -> Vec<VoiceInstruction> {
         let voice_instructions = VoiceInstructionFactory::new(
             current,
             next,
             step_after_next,
-            metric,
+            unit_system,
             POLYLINE_PRECISION,
         );
         voice_instructions.build()
@@ -195,7 +425,7 @@ mod tests {
     fn test_depart() {
         let (current, next, after) =
             load_route_steps("./fixtures/valhalla-short.json", 0, 0, POLYLINE_PRECISION);
-        let instruction = build_instructions(current, next.unwrap(), after, true);
+        let instruction = build_instructions(current, next.unwrap(), after, UnitSystem::Metric);
         assert_json_snapshot!(instruction);
     }
 
@@ -203,7 +433,7 @@ mod tests {
     fn test_basic_step() {
         let (current, next, after) =
             load_route_steps("./fixtures/valhalla-short.json", 0, 1, POLYLINE_PRECISION);
-        let instruction = build_instructions(current, next.unwrap(), after, true);
+        let instruction = build_instructions(current, next.unwrap(), after, UnitSystem::Metric);
         assert_json_snapshot!(instruction);
     }
 
@@ -211,7 +441,7 @@ mod tests {
     fn test_long_step_metric() {
         let (current, next, after) =
             load_route_steps("./fixtures/valhalla-short.json", 0, 2, POLYLINE_PRECISION);
-        let metric = build_instructions(current, next.unwrap(), after, true);
+        let metric = build_instructions(current, next.unwrap(), after, UnitSystem::Metric);
         assert_json_snapshot!(metric);
     }
 
@@ -219,7 +449,7 @@ mod tests {
     fn test_long_step_imperial() {
         let (current, next, after) =
             load_route_steps("./fixtures/valhalla-short.json", 0, 2, POLYLINE_PRECISION);
-        let imperial = build_instructions(current, next.unwrap(), after, false);
+        let imperial = build_instructions(current, next.unwrap(), after, UnitSystem::ImperialUs);
         assert_json_snapshot!(imperial);
     }
 
@@ -227,15 +457,122 @@ mod tests {
     fn test_and_then_step_metric() {
         let (current, next, after) =
             load_route_steps("./fixtures/valhalla-alt.json", 0, 3, POLYLINE_PRECISION);
-        let metric = build_instructions(current, next.unwrap(), after, true);
+        let metric = build_instructions(current, next.unwrap(), after, UnitSystem::Metric);
         assert_json_snapshot!(metric);
     }
 
+    #[test]
+    fn test_ssml_exit_step() {
+        let (current, next, after) =
+            load_route_steps("./fixtures/valhalla-short.json", 0, 2, POLYLINE_PRECISION);
+        let ssml = build_instructions(current, next.unwrap(), after, UnitSystem::ImperialUs)
+            .into_iter()
+            .map(|instruction| instruction.ssml_announcement.unwrap())
+            .collect::<Vec<_>>();
+        assert_json_snapshot!(ssml);
+    }
+
+    #[test]
+    fn test_ssml_prosody() {
+        let (current, next, after) =
+            load_route_steps("./fixtures/valhalla-ramps.json", 0, 3, POLYLINE_PRECISION);
+        let options = SsmlOptions {
+            prosody_rate: Some("1.08".to_string()),
+        };
+        let ssml = VoiceInstructionFactory::new(
+            current,
+            next.unwrap(),
+            after,
+            UnitSystem::Metric,
+            POLYLINE_PRECISION,
+        )
+        .with_ssml_options(options)
+        .build()
+        .into_iter()
+        .map(|instruction| instruction.ssml_announcement.unwrap())
+        .collect::<Vec<_>>();
+        assert_json_snapshot!(ssml);
+    }
+
+    #[test]
+    fn test_long_step_imperial_uk() {
+        let (current, next, after) =
+            load_route_steps("./fixtures/valhalla-short.json", 0, 2, POLYLINE_PRECISION);
+        let imperial_uk = build_instructions(current, next.unwrap(), after, UnitSystem::ImperialUk);
+        assert_json_snapshot!(imperial_uk);
+    }
+
+    #[test]
+    fn test_speed_limit_announcements() {
+        let (current, next, after) =
+            load_route_steps("./fixtures/valhalla-short.json", 0, 2, POLYLINE_PRECISION);
+        let instructions = VoiceInstructionFactory::new(
+            current,
+            next.unwrap(),
+            after,
+            UnitSystem::ImperialUs,
+            POLYLINE_PRECISION,
+        )
+        .with_speed_limit_announcements(true)
+        .build();
+        assert_json_snapshot!(instructions);
+
+        let distances = instructions
+            .iter()
+            .map(|instruction| instruction.distance_along_geometry)
+            .collect::<Vec<_>>();
+        assert!(distances.is_sorted_by(|a, b| a >= b));
+    }
+
+    #[test]
+    fn test_speed_limit_announcements_clash() {
+        let (mut current, next, after) =
+            load_route_steps("./fixtures/valhalla-short.json", 0, 2, POLYLINE_PRECISION);
+
+        // Lower the limit from the coordinate nearest the approach announcement, half a km out.
+        let timeline = SpeedLimitTimeline::for_step(&current, POLYLINE_PRECISION)
+            .unwrap()
+            .unwrap();
+        let step_distance = current.step.distance.unwrap();
+        let change = timeline
+            .segments()
+            .iter()
+            .position(|segment| segment.end >= step_distance - 500.0)
+            .unwrap();
+        let max_speeds = current
+            .annotation
+            .as_mut()
+            .unwrap()
+            .maxspeed
+            .as_mut()
+            .unwrap();
+        for max_speed in &mut max_speeds[change..] {
+            max_speed.speed = Some(89);
+        }
+
+        let factory = VoiceInstructionFactory::new(
+            current,
+            next.unwrap(),
+            after,
+            UnitSystem::Metric,
+            POLYLINE_PRECISION,
+        );
+        let without = factory.build();
+        let with = factory.with_speed_limit_announcements(true).build();
+
+        // Only the change onto the interstate is announced.
+        assert_eq!(with.len(), without.len() + 1);
+        assert!(
+            with.iter()
+                .all(|instruction| instruction.announcement != "Speed limit 89.")
+        );
+    }
+
     #[test]
     fn test_and_then_step_imperial() {
         let (current, next, after) =
             load_route_steps("./fixtures/valhalla-alt.json", 0, 3, POLYLINE_PRECISION);
-        let imperial = build_instructions(current, next.unwrap(), after, false);
+        let imperial = build_instructions(current, next.unwrap(), after, UnitSystem::ImperialUs);
         assert_json_snapshot!(imperial);
     }
 }
@@ -1,10 +1,20 @@
+#[cfg(feature = "cli")]
+pub mod cli;
 pub mod distance;
+pub mod error;
 pub mod geo;
 pub mod instructions;
+pub mod locale;
 pub mod osrm;
+#[cfg(feature = "proxy")]
+pub mod proxy;
+pub mod response;
+pub mod speed_limit;
 pub mod testing;
 
-rust_i18n::i18n!("locales");
+pub use error::Error;
+
+rust_i18n::i18n!("locales", fallback = "en");
 
 #[allow(dead_code)] // Used in tests.
 const POLYLINE_PRECISION: u32 = 6;
@@ -0,0 +1,66 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// The language instructions are generated in.
+///
+/// Templates and units missing from a locale fall back to English.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+    Fr,
+    Es,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 4] = [Locale::En, Locale::De, Locale::Fr, Locale::Es];
+
+    /// The locale key used in `locales/*.yml`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::De => "de",
+            Locale::Fr => "fr",
+            Locale::Es => "es",
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    /// Parses a language tag such as "de" or "de-AT". Only the language subtag is used.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        Locale::ALL
+            .into_iter()
+            .find(|locale| locale.as_str() == language)
+            .ok_or_else(|| format!("Unsupported locale: {}", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("de".parse::<Locale>(), Ok(Locale::De));
+        assert_eq!("fr-CA".parse::<Locale>(), Ok(Locale::Fr));
+        assert_eq!("es_MX".parse::<Locale>(), Ok(Locale::Es));
+        assert_eq!("EN".parse::<Locale>(), Ok(Locale::En));
+        assert!("ja".parse::<Locale>().is_err());
+    }
+}
@@ -1,61 +1,102 @@
+use geo::Coord;
 use osrm_openapi_models::models::{RouteStep, StepManeuver};
 use serde_json::Value;
 
+use crate::{
+    Error,
+    geo::utilities::{get_coordinates_from_geojson, get_coordinates_from_geometry},
+    osrm::signage::{Destinations, parse_exits},
+};
+
 /// Extension trait for RouteStep to safely extract strings from Value fields
 pub trait RouteStepExt {
     /// Extract geometry as a string, returning an error for unsupported object types
-    fn geometry_string(&self) -> Result<Option<String>, String>;
+    fn geometry_string(&self) -> Result<Option<String>, Error>;
+
+    /// Decode the geometry into coordinates, whether it is an encoded polyline of
+    /// `polyline_precision` or a GeoJSON LineString
+    fn geometry_coordinates(&self, polyline_precision: u32) -> Result<Option<Vec<Coord>>, Error>;
 
     /// Extract destinations as a string, returning an error for unsupported object types
-    fn destinations_string(&self) -> Result<Option<String>, String>;
+    fn destinations_string(&self) -> Result<Option<String>, Error>;
 
     /// Extract exits as a string, returning an error for unsupported object types
-    fn exits_string(&self) -> Result<Option<String>, String>;
+    fn exits_string(&self) -> Result<Option<String>, Error>;
+
+    /// Parse the signed destinations into their refs and names
+    fn signed_destinations(&self) -> Result<Option<Destinations>, Error>;
+
+    /// Parse the exits into a list of exit numbers
+    fn exit_numbers(&self) -> Result<Vec<String>, Error>;
 }
 
 /// Extension trait for StepManeuver to safely extract strings from Value fields
 pub trait StepManeuverExt {
     /// Extract instruction as a string, returning an error for unsupported object types
-    fn instruction_string(&self) -> Result<Option<String>, String>;
+    fn instruction_string(&self) -> Result<Option<String>, Error>;
 }
 
 impl RouteStepExt for RouteStep {
-    fn geometry_string(&self) -> Result<Option<String>, String> {
+    fn geometry_string(&self) -> Result<Option<String>, Error> {
         value_to_string(self.geometry.as_ref(), "geometry")
     }
 
-    fn destinations_string(&self) -> Result<Option<String>, String> {
+    fn geometry_coordinates(&self, polyline_precision: u32) -> Result<Option<Vec<Coord>>, Error> {
+        match self.geometry.as_ref() {
+            Some(geometry @ Value::Object(_)) => get_coordinates_from_geojson(geometry).map(Some),
+            _ => self
+                .geometry_string()?
+                .map(|geometry| get_coordinates_from_geometry(&geometry, polyline_precision))
+                .transpose(),
+        }
+    }
+
+    fn destinations_string(&self) -> Result<Option<String>, Error> {
         value_to_string(self.destinations.as_ref(), "destinations")
     }
 
-    fn exits_string(&self) -> Result<Option<String>, String> {
+    fn exits_string(&self) -> Result<Option<String>, Error> {
         value_to_string(self.exits.as_ref(), "exits")
     }
+
+    fn signed_destinations(&self) -> Result<Option<Destinations>, Error> {
+        self.destinations
+            .as_ref()
+            .map_or(Ok(None), Destinations::from_value)
+    }
+
+    fn exit_numbers(&self) -> Result<Vec<String>, Error> {
+        self.exits.as_ref().map_or(Ok(Vec::new()), parse_exits)
+    }
 }
 
 impl StepManeuverExt for StepManeuver {
-    fn instruction_string(&self) -> Result<Option<String>, String> {
+    fn instruction_string(&self) -> Result<Option<String>, Error> {
         // instruction is already a String, not a Value
         Ok(self.instruction.clone())
     }
 }
 
 /// Helper function to safely convert a Value to a String
-fn value_to_string(value: Option<&Value>, field_name: &str) -> Result<Option<String>, String> {
+fn value_to_string(
+    value: Option<&Value>,
+    field_name: &'static str,
+) -> Result<Option<String>, Error> {
     match value {
         None => Ok(None),
         Some(Value::String(s)) => Ok(Some(s.clone())),
         Some(Value::Null) => Ok(None),
-        Some(Value::Object(_)) => Err(format!(
-            "TODO: {} field contains a JSON object, which is not yet supported. \
-                 Please file an issue with your use case.",
-            field_name
-        )),
-        Some(Value::Array(_)) => Err(format!(
-            "TODO: {} field contains a JSON array, which is not yet supported. \
-                 Please file an issue with your use case.",
-            field_name
-        )),
+        Some(Value::Object(_)) if field_name == "geometry" => {
+            Err(Error::UnsupportedGeoJson { field: field_name })
+        }
+        Some(Value::Object(_)) => Err(Error::UnsupportedValue {
+            field: field_name,
+            kind: "object",
+        }),
+        Some(Value::Array(_)) => Err(Error::UnsupportedValue {
+            field: field_name,
+            kind: "array",
+        }),
         Some(Value::Number(n)) => Ok(Some(n.to_string())),
         Some(Value::Bool(b)) => Ok(Some(b.to_string())),
     }
@@ -102,21 +143,45 @@ mod tests {
             geometry: Some(json!({"type": "LineString", "coordinates": [[0, 0]]})),
             ..Default::default()
         };
-        assert!(step.geometry_string().is_err());
-        assert!(
-            step.geometry_string()
-                .unwrap_err()
-                .contains("not yet supported")
+        assert_eq!(
+            step.geometry_string(),
+            Err(Error::UnsupportedGeoJson { field: "geometry" })
         );
     }
 
+    #[test]
+    fn test_geometry_coordinates() {
+        let polyline = RouteStep {
+            geometry: Some(json!("epxkF|`miVfAfA~@~@")),
+            ..Default::default()
+        };
+        let geojson = RouteStep {
+            geometry: Some(json!({
+                "type": "LineString",
+                "coordinates": [[-122.29663, 38.79699], [-122.29699, 38.79663], [-122.29731, 38.79631]]
+            })),
+            ..Default::default()
+        };
+
+        let coordinates = polyline.geometry_coordinates(5).unwrap().unwrap();
+        assert_eq!(coordinates.len(), 3);
+        assert_eq!(geojson.geometry_coordinates(5).unwrap(), Some(coordinates));
+        assert_eq!(RouteStep::default().geometry_coordinates(5), Ok(None));
+    }
+
     #[test]
     fn test_geometry_string_with_array_returns_error() {
         let step = RouteStep {
             geometry: Some(json!(["point1", "point2"])),
             ..Default::default()
         };
-        assert!(step.geometry_string().is_err());
+        assert_eq!(
+            step.geometry_string(),
+            Err(Error::UnsupportedValue {
+                field: "geometry",
+                kind: "array"
+            })
+        );
     }
 
     #[test]
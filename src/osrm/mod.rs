@@ -1,6 +1,8 @@
 use osrm_openapi_models::models::{Annotation, RouteStep};
 
 pub mod extensions;
+pub mod models;
+pub mod signage;
 pub mod utilities;
 
 pub use extensions::{RouteStepExt, StepManeuverExt};
@@ -9,6 +11,26 @@ pub use extensions::{RouteStepExt, StepManeuverExt};
 pub struct RouteStepBundle {
     pub step: RouteStep,
     pub annotation: Option<Box<Annotation>>,
+    /// Index of the leg this step belongs to.
+    pub leg_index: usize,
+    /// Index of the step within its leg.
+    pub step_index: usize,
+    /// Start of the step's segments within the leg's annotation and geometry.
     pub start_index: usize,
+    /// End (inclusive) of the step's segments within the leg's annotation and geometry.
     pub end_index: usize,
+    /// The step's intersections, once checked against its geometry with
+    /// [`crate::instructions::InstructionsFactory::with_intersection_validation`].
+    pub intersections: Vec<IntersectionPosition>,
+}
+
+/// Where an intersection is along its step.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntersectionPosition {
+    /// Index of the intersection within the step's intersections.
+    pub intersection_index: usize,
+    /// Index of the intersection's coordinate within the leg's geometry.
+    pub geometry_index: usize,
+    /// Meters from the start of the step to the intersection.
+    pub distance_along_step: f64,
 }
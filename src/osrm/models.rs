@@ -0,0 +1,160 @@
+use osrm_openapi_models::models::{
+    BannerContent, BannerContentComponentsInner, BannerInstruction, ManeuverModifier, ManeuverType,
+    VoiceInstruction,
+};
+use serde::{Deserialize, Serialize};
+
+/// A banner instruction following the Mapbox Directions banner model.
+///
+/// This extends `osrm_openapi_models::models::BannerInstruction` with the `sub` banner,
+/// which the OpenAPI model does not describe. Converting into the OpenAPI model drops it.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExtendedBannerInstruction {
+    #[serde(rename = "distanceAlongGeometry")]
+    pub distance_along_geometry: f64,
+    #[serde(rename = "primary")]
+    pub primary: ExtendedBannerContent,
+    #[serde(rename = "secondary", skip_serializing_if = "Option::is_none")]
+    pub secondary: Option<ExtendedBannerContent>,
+    #[serde(rename = "sub", skip_serializing_if = "Option::is_none")]
+    pub sub: Option<ExtendedBannerContent>,
+}
+
+/// Banner content whose components can describe lanes.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExtendedBannerContent {
+    #[serde(rename = "text")]
+    pub text: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<ManeuverType>,
+    #[serde(rename = "modifier", skip_serializing_if = "Option::is_none")]
+    pub modifier: Option<ManeuverModifier>,
+    #[serde(rename = "components", skip_serializing_if = "Option::is_none")]
+    pub components: Option<Vec<ExtendedBannerComponent>>,
+}
+
+/// A banner component. Lane components (`"type": "lane"`) carry the lane's
+/// `directions` and whether it is `active` for the maneuver.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExtendedBannerComponent {
+    #[serde(rename = "text", skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<String>,
+    #[serde(rename = "directions", skip_serializing_if = "Option::is_none")]
+    pub directions: Option<Vec<String>>,
+    #[serde(rename = "active", skip_serializing_if = "Option::is_none")]
+    pub active: Option<bool>,
+    #[serde(rename = "active_direction", skip_serializing_if = "Option::is_none")]
+    pub active_direction: Option<String>,
+}
+
+impl ExtendedBannerComponent {
+    pub fn new(r#type: &str, text: String) -> Self {
+        ExtendedBannerComponent {
+            text: Some(text),
+            r#type: Some(r#type.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<ExtendedBannerInstruction> for BannerInstruction {
+    fn from(banner: ExtendedBannerInstruction) -> Self {
+        BannerInstruction {
+            distance_along_geometry: banner.distance_along_geometry,
+            primary: Box::new(banner.primary.into()),
+            secondary: banner.secondary.map(|content| Box::new(content.into())),
+        }
+    }
+}
+
+impl From<ExtendedBannerContent> for BannerContent {
+    fn from(content: ExtendedBannerContent) -> Self {
+        BannerContent {
+            text: content.text,
+            r#type: content.r#type,
+            modifier: content.modifier,
+            components: content
+                .components
+                .map(|components| components.into_iter().map(Into::into).collect()),
+        }
+    }
+}
+
+impl From<ExtendedBannerComponent> for BannerContentComponentsInner {
+    fn from(component: ExtendedBannerComponent) -> Self {
+        BannerContentComponentsInner {
+            text: component.text,
+            r#type: component.r#type,
+        }
+    }
+}
+
+/// A route response's legs with the fields the OpenAPI model drops.
+///
+/// Deserialize it from the same JSON as the `osrm_openapi_models::models::Route`.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExtendedRoute {
+    #[serde(rename = "legs", skip_serializing_if = "Option::is_none")]
+    pub legs: Option<Vec<ExtendedRouteLeg>>,
+}
+
+/// The fields of a route leg which the OpenAPI model does not describe.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExtendedRouteLeg {
+    /// The countries the leg passes through.
+    #[serde(rename = "admins", skip_serializing_if = "Option::is_none")]
+    pub admins: Option<Vec<Admin>>,
+    #[serde(rename = "steps", skip_serializing_if = "Option::is_none")]
+    pub steps: Option<Vec<ExtendedRouteStep>>,
+}
+
+/// The fields of a route step which the OpenAPI model does not describe.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExtendedRouteStep {
+    /// The unit speed limits are posted in on the step, "mph" or "km/h".
+    #[serde(rename = "speedLimitUnit", skip_serializing_if = "Option::is_none")]
+    pub speed_limit_unit: Option<String>,
+    /// The style of the step's speed limit signs, "mutcd" or "vienna".
+    #[serde(rename = "speedLimitSign", skip_serializing_if = "Option::is_none")]
+    pub speed_limit_sign: Option<String>,
+    #[serde(rename = "intersections", skip_serializing_if = "Option::is_none")]
+    pub intersections: Option<Vec<ExtendedIntersection>>,
+    /// The step's banners with their `sub` banner and lanes.
+    #[serde(rename = "bannerInstructions", skip_serializing_if = "Option::is_none")]
+    pub banner_instructions: Option<Vec<ExtendedBannerInstruction>>,
+    #[serde(rename = "voiceInstructions", skip_serializing_if = "Option::is_none")]
+    pub voice_instructions: Option<Vec<VoiceInstruction>>,
+}
+
+/// The fields of an intersection which the OpenAPI model does not describe.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExtendedIntersection {
+    /// Index of the intersection's coordinate within the leg's geometry.
+    #[serde(rename = "geometry_index", skip_serializing_if = "Option::is_none")]
+    pub geometry_index: Option<usize>,
+    #[serde(rename = "location", skip_serializing_if = "Option::is_none")]
+    pub location: Option<Vec<f64>>,
+    /// Index of the intersection's country within the leg's admins.
+    #[serde(rename = "admin_index", skip_serializing_if = "Option::is_none")]
+    pub admin_index: Option<usize>,
+}
+
+/// An administrative region, identified by its ISO 3166-1 country codes.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Admin {
+    #[serde(rename = "iso_3166_1", skip_serializing_if = "Option::is_none")]
+    pub iso_3166_1: Option<String>,
+    #[serde(rename = "iso_3166_1_alpha3", skip_serializing_if = "Option::is_none")]
+    pub iso_3166_1_alpha3: Option<String>,
+}
+
+impl Admin {
+    /// The region's country code, preferring the two letter code.
+    pub fn country_code(&self) -> Option<&str> {
+        self.iso_3166_1
+            .as_deref()
+            .or(self.iso_3166_1_alpha3.as_deref())
+    }
+}
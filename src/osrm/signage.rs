@@ -0,0 +1,149 @@
+use serde_json::Value;
+
+use crate::Error;
+
+/// The destinations signed for a step.
+///
+/// OSRM formats them as "refs: names", e.g. "I 70 Business Loop, US 6: Clifton, Grand Junction".
+/// Without a colon, every entry is a name.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct Destinations {
+    /// Route refs the destinations are reached by, e.g. "US 6".
+    pub refs: Vec<String>,
+    /// Places the destinations lead to, e.g. "Grand Junction".
+    pub names: Vec<String>,
+}
+
+impl Destinations {
+    pub fn parse(destinations: &str) -> Self {
+        match destinations.split_once(':') {
+            Some((refs, names)) => Destinations {
+                refs: split_list(refs, ','),
+                names: split_list(names, ','),
+            },
+            None => Destinations {
+                refs: Vec::new(),
+                names: split_list(destinations, ','),
+            },
+        }
+    }
+
+    /// Parses a destinations string, or an array of them which some servers return.
+    pub fn from_value(value: &Value) -> Result<Option<Self>, Error> {
+        let destinations = match value {
+            Value::Null => return Ok(None),
+            Value::String(destinations) => Destinations::parse(destinations),
+            Value::Array(entries) => {
+                entries
+                    .iter()
+                    .try_fold(Destinations::default(), |mut destinations, entry| {
+                        let entry = Destinations::from_value(entry)?.unwrap_or_default();
+                        destinations.refs.extend(entry.refs);
+                        destinations.names.extend(entry.names);
+                        Ok(destinations)
+                    })?
+            }
+            Value::Object(_) => {
+                return Err(Error::UnsupportedValue {
+                    field: "destinations",
+                    kind: "object",
+                });
+            }
+            Value::Number(_) | Value::Bool(_) => Destinations::parse(&value.to_string()),
+        };
+
+        Ok(Some(destinations).filter(|destinations| !destinations.is_empty()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.refs.is_empty() && self.names.is_empty()
+    }
+}
+
+/// Parses a step's exit numbers. OSRM separates multiple exits with ";", e.g. "37;37A".
+pub fn parse_exits(value: &Value) -> Result<Vec<String>, Error> {
+    match value {
+        Value::Null => Ok(Vec::new()),
+        Value::String(exits) => Ok(split_list(exits, ';')),
+        Value::Number(exit) => Ok(vec![exit.to_string()]),
+        Value::Array(entries) => entries.iter().try_fold(Vec::new(), |mut exits, entry| {
+            exits.extend(parse_exits(entry)?);
+            Ok(exits)
+        }),
+        Value::Object(_) => Err(Error::UnsupportedValue {
+            field: "exits",
+            kind: "object",
+        }),
+        Value::Bool(_) => Err(Error::UnsupportedValue {
+            field: "exits",
+            kind: "boolean",
+        }),
+    }
+}
+
+fn split_list(list: &str, separator: char) -> Vec<String> {
+    list.split(separator)
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_destinations() {
+        assert_eq!(
+            Destinations::parse("I 70 Business Loop, US 6, US 50: Clifton, Grand Junction, Delta"),
+            Destinations {
+                refs: vec![
+                    "I 70 Business Loop".to_string(),
+                    "US 6".to_string(),
+                    "US 50".to_string()
+                ],
+                names: vec![
+                    "Clifton".to_string(),
+                    "Grand Junction".to_string(),
+                    "Delta".to_string()
+                ],
+            }
+        );
+        assert_eq!(
+            Destinations::parse("Indiana Street, Denver West"),
+            Destinations {
+                refs: Vec::new(),
+                names: vec!["Indiana Street".to_string(), "Denver West".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_destinations_from_value() {
+        assert_eq!(
+            Destinations::from_value(&json!(["US 6: Clifton", "Grand Junction"])),
+            Ok(Some(Destinations {
+                refs: vec!["US 6".to_string()],
+                names: vec!["Clifton".to_string(), "Grand Junction".to_string()],
+            }))
+        );
+        assert_eq!(Destinations::from_value(&json!("")), Ok(None));
+        assert!(Destinations::from_value(&json!({"name": "Clifton"})).is_err());
+    }
+
+    #[test]
+    fn test_parse_exits() {
+        assert_eq!(
+            parse_exits(&json!("37; 37A")),
+            Ok(vec!["37".to_string(), "37A".to_string()])
+        );
+        assert_eq!(parse_exits(&json!(37)), Ok(vec!["37".to_string()]));
+        assert_eq!(
+            parse_exits(&json!(["12", "12B"])),
+            Ok(vec!["12".to_string(), "12B".to_string()])
+        );
+        assert_eq!(parse_exits(&json!(null)), Ok(Vec::new()));
+    }
+}
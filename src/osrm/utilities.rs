@@ -1,83 +1,319 @@
-use osrm_openapi_models::models::{Annotation, Route};
+use geo::{Distance, Haversine, Length, LineString, Point};
+use osrm_openapi_models::models::{Annotation, ManeuverType, Route, RouteLeg, RouteStep};
+use serde_json::Value;
 
-use crate::geo::utilities::get_coordinates_from_geometry;
+use crate::{Error, geo::step_geometry::StepGeometry};
 
-use super::{RouteStepBundle, RouteStepExt};
+use super::{
+    IntersectionPosition, RouteStepBundle, RouteStepExt,
+    models::{ExtendedRouteLeg, ExtendedRouteStep},
+};
 
+/// Builds the step bundles for every leg of a route, indexed by `[leg][step]`.
+///
+/// Annotations and geometry indices are per leg in OSRM, so each leg's bundles
+/// start counting from zero and slice that leg's own annotation.
 pub(crate) fn get_step_bundles(
     route: &Route,
     polyline_precision: u32,
-) -> Option<Vec<RouteStepBundle>> {
-    let annotations = route
-        .clone()
-        .legs?
+) -> Result<Vec<Vec<RouteStepBundle>>, Error> {
+    route
+        .legs
+        .as_ref()
+        .ok_or(Error::MissingLegs)?
         .iter()
-        .flat_map(|l| l.annotation.clone())
-        .next();
+        .enumerate()
+        .map(|(leg_index, leg)| get_leg_step_bundles(leg, leg_index, polyline_precision))
+        .collect()
+}
 
+fn get_leg_step_bundles(
+    leg: &RouteLeg,
+    leg_index: usize,
+    polyline_precision: u32,
+) -> Result<Vec<RouteStepBundle>, Error> {
     let mut current_index = 0;
 
-    let steps = route
-        .legs
-        .as_ref()?
+    let bundles = leg
+        .steps
         .iter()
-        .flat_map(|l| l.steps.clone())
         .flatten()
-        .map(|step| {
+        .enumerate()
+        .map(|(step_index, step)| {
             let coord_len = step
-                .geometry_string()
-                .ok()
-                .flatten()
-                .and_then(|g| get_coordinates_from_geometry(&g, polyline_precision))
-                .map(|c| c.len())
-                .unwrap_or(0);
+                .maneuver
+                .as_ref()
+                .ok_or(Error::MissingManeuver)
+                .and_then(|_| step_coordinate_count(step, polyline_precision))
+                .map_err(|error| error.in_step(leg_index, step_index))?;
+
+            // The arrive step's geometry repeats the destination, so it adds no annotated segment.
+            let arrives = step
+                .maneuver
+                .as_ref()
+                .is_some_and(|maneuver| maneuver.r#type == Some(ManeuverType::Arrive));
 
             let start_index = current_index;
-            let (end_index, annotation_slice) = if coord_len >= 2 {
+            let (end_index, annotation_slice) = if coord_len >= 2 && !arrives {
                 let annotation_len = coord_len - 1; // annotations are between coordinates
                 let end_index = current_index + annotation_len - 1;
                 current_index = end_index + 1;
-                (
-                    end_index,
-                    get_annotation_slice(annotations.clone(), start_index, end_index),
-                )
+                let annotation_slice =
+                    get_annotation_slice(leg.annotation.as_deref(), start_index, end_index)
+                        .map_err(|error| error.in_step(leg_index, step_index))?;
+                (end_index, annotation_slice)
             } else {
                 // Steps with 0 or 1 coordinates have no annotation segments
                 (start_index, None)
             };
 
-            RouteStepBundle {
-                step,
+            Ok(RouteStepBundle {
+                step: step.clone(),
                 annotation: annotation_slice,
+                leg_index,
+                step_index,
                 start_index,
                 end_index,
+                intersections: Vec::new(),
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    if let Some(actual) = leg.annotation.as_deref().and_then(annotation_len)
+        && actual != current_index
+    {
+        return Err(Error::AnnotationLengthMismatch {
+            leg_index,
+            expected: current_index,
+            actual,
+        });
+    }
+
+    Ok(bundles)
+}
+
+fn step_coordinate_count(step: &RouteStep, polyline_precision: u32) -> Result<usize, Error> {
+    let coordinates = step
+        .geometry_coordinates(polyline_precision)?
+        .ok_or(Error::MissingGeometry)?;
+    Ok(coordinates.len())
+}
+
+/// How far in meters an intersection may be from the step coordinate at its geometry index.
+const INTERSECTION_LOCATION_TOLERANCE: f64 = 5.0;
+
+/// Cross-checks each step's intersections against the coordinates counted for its bundle, and
+/// records how far along the step each of them is.
+///
+/// `legs` are read from the same route JSON, since the OpenAPI model drops `geometry_index`, and
+/// must have the bundles' legs and steps. An intersection's index must be one of its step's
+/// coordinates, and the one at its location. Intersections without an index are left out.
+pub(crate) fn snap_intersections(
+    bundles: &mut [Vec<RouteStepBundle>],
+    legs: &[ExtendedRouteLeg],
+    polyline_precision: u32,
+) -> Result<(), Error> {
+    if bundles.len() != legs.len() {
+        return Err(Error::LegCountMismatch {
+            expected: bundles.len(),
+            actual: legs.len(),
+        });
+    }
+
+    for (leg_index, (leg_bundles, leg)) in bundles.iter_mut().zip(legs).enumerate() {
+        let steps = leg.steps.as_deref().unwrap_or_default();
+        if leg_bundles.len() != steps.len() {
+            return Err(Error::StepCountMismatch {
+                leg_index,
+                expected: leg_bundles.len(),
+                actual: steps.len(),
+            });
+        }
+
+        for (bundle, step) in leg_bundles.iter_mut().zip(steps) {
+            bundle.intersections = step_intersections(bundle, step, polyline_precision)
+                .map_err(|error| error.in_step(bundle.leg_index, bundle.step_index))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn step_intersections(
+    bundle: &RouteStepBundle,
+    step: &ExtendedRouteStep,
+    polyline_precision: u32,
+) -> Result<Vec<IntersectionPosition>, Error> {
+    let geometry =
+        StepGeometry::from_step(&bundle.step, polyline_precision)?.ok_or(Error::MissingGeometry)?;
+    let first = bundle.start_index;
+    let last = first + geometry.coordinates().len().saturating_sub(1);
+
+    step.intersections
+        .iter()
+        .flatten()
+        .enumerate()
+        .filter_map(|(intersection_index, intersection)| {
+            intersection
+                .geometry_index
+                .map(|geometry_index| (intersection_index, intersection, geometry_index))
+        })
+        .map(|(intersection_index, intersection, geometry_index)| {
+            let mismatch = Error::IntersectionIndexMismatch {
+                intersection_index,
+                geometry_index,
+                first,
+                last,
+            };
+
+            let offset = geometry_index.checked_sub(first).ok_or(mismatch.clone())?;
+            let coordinate = *geometry.coordinates().get(offset).ok_or(mismatch.clone())?;
+            if let Some([longitude, latitude, ..]) = intersection.location.as_deref()
+                && Haversine.distance(Point::from(coordinate), Point::new(*longitude, *latitude))
+                    > INTERSECTION_LOCATION_TOLERANCE
+            {
+                return Err(mismatch);
             }
+
+            Ok(IntersectionPosition {
+                intersection_index,
+                geometry_index,
+                distance_along_step: geometry.distances()[offset],
+            })
         })
-        .collect();
-    Some(steps)
+        .collect()
 }
 
+/// The number of segments an annotation covers, from the first of its per segment values.
+fn annotation_len(annotation: &Annotation) -> Option<usize> {
+    annotation
+        .distance
+        .as_ref()
+        .map(Vec::len)
+        .or_else(|| annotation.duration.as_ref().map(Vec::len))
+        .or_else(|| annotation.speed.as_ref().map(Vec::len))
+        .or_else(|| annotation.weight.as_ref().map(Vec::len))
+        .or_else(|| annotation.maxspeed.as_ref().map(Vec::len))
+}
+
+/// Precisions servers encode polylines with, 5 for OSRM and 6 for Valhalla.
+const POLYLINE_PRECISIONS: [u32; 2] = [5, 6];
+
+/// How far in meters a step's first coordinate may be from its maneuver location.
+const MANEUVER_LOCATION_TOLERANCE: f64 = 25.0;
+
+/// How far a step's geometry may be longer or shorter than its distance, in meters or as a
+/// share of the distance, whichever is larger.
+const STEP_DISTANCE_TOLERANCE: f64 = 50.0;
+const STEP_DISTANCE_TOLERANCE_PCT: f64 = 0.5;
+
+/// Detects the precision the route's step polylines are encoded with.
+///
+/// Decoding with the wrong precision scales every coordinate by ten, so a precision fits when
+/// the decoded coordinates are valid and each step starts at its maneuver location and is about
+/// as long as its distance. `fallback` is kept when it fits, or when the route has no polylines
+/// to tell from.
+pub fn detect_polyline_precision(route: &Route, fallback: u32) -> Result<u32, Error> {
+    let steps = route
+        .legs
+        .iter()
+        .flatten()
+        .flat_map(|leg| leg.steps.iter().flatten())
+        .filter(|step| matches!(step.geometry, Some(Value::String(_))))
+        .collect::<Vec<_>>();
+
+    let fits = |precision: u32| steps.iter().all(|step| step_fits(step, precision));
+
+    if steps.is_empty() || fits(fallback) {
+        return Ok(fallback);
+    }
+
+    POLYLINE_PRECISIONS
+        .into_iter()
+        .find(|precision| *precision != fallback && fits(*precision))
+        .ok_or(Error::UnknownPolylinePrecision)
+}
+
+/// Whether the step's polyline decoded at `polyline_precision` agrees with its maneuver
+/// location and distance.
+fn step_fits(step: &RouteStep, polyline_precision: u32) -> bool {
+    let Ok(Some(coordinates)) = step.geometry_coordinates(polyline_precision) else {
+        return false;
+    };
+
+    let valid = coordinates
+        .iter()
+        .all(|coord| (-180.0..=180.0).contains(&coord.x) && (-90.0..=90.0).contains(&coord.y));
+
+    let location = step
+        .maneuver
+        .as_ref()
+        .and_then(|maneuver| maneuver.location.as_deref());
+    let starts_at_location = match (coordinates.first(), location) {
+        (Some(first), Some([longitude, latitude, ..])) => {
+            Haversine.distance(Point::from(*first), Point::new(*longitude, *latitude))
+                <= MANEUVER_LOCATION_TOLERANCE
+        }
+        _ => true,
+    };
+
+    let has_distance = step.distance.is_none_or(|distance| {
+        let length = Haversine.length(&LineString::<f64>::from(coordinates.clone()));
+        (length - distance).abs()
+            <= STEP_DISTANCE_TOLERANCE.max(distance * STEP_DISTANCE_TOLERANCE_PCT)
+    });
+
+    valid && starts_at_location && has_distance
+}
+
+/// The annotation of the segments `start_index` through `end_index` (inclusive), failing with
+/// [`Error::AnnotationOutOfBounds`] when an annotation ends before them.
 pub(crate) fn get_annotation_slice(
-    annotations: Option<Box<Annotation>>,
+    annotation: Option<&Annotation>,
+    start_index: usize,
+    end_index: usize,
+) -> Result<Option<Box<Annotation>>, Error> {
+    let Some(annotation) = annotation else {
+        return Ok(None);
+    };
+
+    Ok(Some(Box::new(Annotation {
+        distance: slice(&annotation.distance, "distance", start_index, end_index)?,
+        duration: slice(&annotation.duration, "duration", start_index, end_index)?,
+        datasources: slice(
+            &annotation.datasources,
+            "datasources",
+            start_index,
+            end_index,
+        )?,
+        nodes: slice(&annotation.nodes, "nodes", start_index, end_index)?,
+        weight: slice(&annotation.weight, "weight", start_index, end_index)?,
+        speed: slice(&annotation.speed, "speed", start_index, end_index)?,
+        maxspeed: slice(&annotation.maxspeed, "maxspeed", start_index, end_index)?,
+        metadata: annotation.metadata.clone(),
+    })))
+}
+
+/// Slices an annotation's `field` values from `start_index` through `end_index` (inclusive).
+fn slice<T: Clone>(
+    values: &Option<Vec<T>>,
+    field: &'static str,
     start_index: usize,
     end_index: usize,
-) -> Option<Box<Annotation>> {
-    if start_index >= end_index {
-        return None;
-    }
-
-    annotations.map(|ann| {
-        Box::new(Annotation {
-            distance: ann.distance.map(|v| v[start_index..=end_index].to_vec()),
-            duration: ann.duration.map(|v| v[start_index..=end_index].to_vec()),
-            datasources: ann.datasources.map(|v| v[start_index..=end_index].to_vec()),
-            nodes: ann.nodes.map(|v| v[start_index..=end_index].to_vec()),
-            weight: ann.weight.map(|v| v[start_index..=end_index].to_vec()),
-            speed: ann.speed.map(|v| v[start_index..=end_index].to_vec()),
-            maxspeed: ann.maxspeed.map(|v| v[start_index..=end_index].to_vec()),
-            metadata: ann.metadata.clone(),
+) -> Result<Option<Vec<T>>, Error> {
+    let Some(values) = values else {
+        return Ok(None);
+    };
+
+    values
+        .get(start_index..=end_index)
+        .map(|values| Some(values.to_vec()))
+        .ok_or(Error::AnnotationOutOfBounds {
+            field,
+            start: start_index,
+            end: end_index,
+            len: values.len(),
         })
-    })
 }
 
 #[cfg(test)]
@@ -85,8 +321,9 @@ mod tests {
     use crate::POLYLINE_PRECISION;
 
     use super::*;
-    use crate::testing::load_route;
+    use crate::testing::{fixtures::load_extended_route, load_route};
     use insta::assert_debug_snapshot;
+    use polyline::encode_coordinates;
 
     #[test]
     fn test_get_step_bundle_index_ranges() {
@@ -95,6 +332,7 @@ mod tests {
 
         let index_ranges = step_bundles
             .iter()
+            .flatten()
             .map(|sb| (sb.start_index, sb.end_index))
             .collect::<Vec<(usize, usize)>>();
 
@@ -108,9 +346,268 @@ mod tests {
 
         let annotations = step_bundles
             .iter()
+            .flatten()
             .map(|sb| sb.annotation.clone())
             .collect::<Vec<_>>();
 
         assert_debug_snapshot!(annotations);
     }
+
+    #[test]
+    fn test_get_step_bundles_multi_leg() {
+        let route = load_route("./fixtures/valhalla-legs.json", 0);
+        let step_bundles = get_step_bundles(&route, POLYLINE_PRECISION).unwrap();
+
+        let keys = step_bundles
+            .iter()
+            .flatten()
+            .map(|sb| {
+                (
+                    sb.leg_index,
+                    sb.step_index,
+                    sb.start_index,
+                    sb.end_index,
+                    sb.annotation
+                        .as_ref()
+                        .and_then(|a| a.speed.as_ref())
+                        .map(|s| s.len()),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_debug_snapshot!(keys);
+    }
+
+    #[test]
+    fn test_get_step_bundles_second_leg_annotation() {
+        let route = load_route("./fixtures/valhalla-legs.json", 0);
+        let step_bundles = get_step_bundles(&route, POLYLINE_PRECISION).unwrap();
+        let legs = route.legs.unwrap();
+
+        // The second leg's first step starts at the beginning of the second leg's annotation.
+        let first = &step_bundles[1][0];
+        let leg_speeds = legs[1].annotation.as_ref().unwrap().speed.clone().unwrap();
+        let step_speeds = first.annotation.as_ref().unwrap().speed.clone().unwrap();
+        assert_eq!(first.start_index, 0);
+        assert_eq!(step_speeds[..], leg_speeds[..step_speeds.len()]);
+    }
+
+    #[test]
+    fn test_get_step_bundles_missing_legs() {
+        let route = Route::default();
+        assert_eq!(
+            get_step_bundles(&route, POLYLINE_PRECISION),
+            Err(Error::MissingLegs)
+        );
+    }
+
+    #[test]
+    fn test_get_step_bundles_missing_maneuver() {
+        let mut route = load_route("./fixtures/valhalla-legs.json", 0);
+        route.legs.as_mut().unwrap()[0].steps.as_mut().unwrap()[1].maneuver = None;
+
+        assert_eq!(
+            get_step_bundles(&route, POLYLINE_PRECISION),
+            Err(Error::MissingManeuver.in_step(0, 1))
+        );
+    }
+
+    #[test]
+    fn test_get_step_bundles_missing_geometry() {
+        let mut route = load_route("./fixtures/valhalla-legs.json", 0);
+        route.legs.as_mut().unwrap()[1].steps.as_mut().unwrap()[2].geometry = None;
+
+        assert_eq!(
+            get_step_bundles(&route, POLYLINE_PRECISION),
+            Err(Error::MissingGeometry.in_step(1, 2))
+        );
+    }
+
+    fn load_extended_legs(path: &str) -> Vec<ExtendedRouteLeg> {
+        load_extended_route(path, 0).legs.unwrap()
+    }
+
+    #[test]
+    fn test_snap_intersections() {
+        let route = load_route("./fixtures/valhalla-legs.json", 0);
+        let mut step_bundles = get_step_bundles(&route, POLYLINE_PRECISION).unwrap();
+        let legs = load_extended_legs("./fixtures/valhalla-legs.json");
+        snap_intersections(&mut step_bundles, &legs, POLYLINE_PRECISION).unwrap();
+
+        // Every intersection is placed, each step's first at its start.
+        for (leg_bundles, leg) in step_bundles.iter().zip(&legs) {
+            for (bundle, step) in leg_bundles.iter().zip(leg.steps.iter().flatten()) {
+                assert_eq!(
+                    bundle.intersections.len(),
+                    step.intersections.as_ref().unwrap().len()
+                );
+                assert_eq!(bundle.intersections[0].geometry_index, bundle.start_index);
+                assert_eq!(bundle.intersections[0].distance_along_step, 0.0);
+            }
+        }
+
+        let positions = step_bundles[1][1]
+            .intersections
+            .iter()
+            .map(|position| (position.geometry_index, position.distance_along_step))
+            .take(4)
+            .collect::<Vec<_>>();
+        assert_debug_snapshot!(positions);
+    }
+
+    #[test]
+    fn test_snap_intersections_mismatch() {
+        let route = load_route("./fixtures/valhalla-short.json", 0);
+        let mut step_bundles = get_step_bundles(&route, POLYLINE_PRECISION).unwrap();
+        let mut legs = load_extended_legs("./fixtures/valhalla-short.json");
+
+        // One coordinate off, the index no longer matches the intersection's location.
+        let intersection = &mut legs[0].steps.as_mut().unwrap()[1]
+            .intersections
+            .as_mut()
+            .unwrap()[2];
+        intersection.geometry_index = Some(8);
+
+        assert_eq!(
+            snap_intersections(&mut step_bundles, &legs, POLYLINE_PRECISION),
+            Err(Error::IntersectionIndexMismatch {
+                intersection_index: 2,
+                geometry_index: 8,
+                first: 5,
+                last: 13,
+            }
+            .in_step(0, 1))
+        );
+
+        // Past the end of the step.
+        legs[0].steps.as_mut().unwrap()[1]
+            .intersections
+            .as_mut()
+            .unwrap()[2]
+            .geometry_index = Some(14);
+        assert!(matches!(
+            snap_intersections(&mut step_bundles, &legs, POLYLINE_PRECISION),
+            Err(Error::Step { source, .. }) if matches!(*source, Error::IntersectionIndexMismatch { geometry_index: 14, .. })
+        ));
+    }
+
+    #[test]
+    fn test_snap_intersections_count_mismatch() {
+        let route = load_route("./fixtures/valhalla-legs.json", 0);
+        let mut step_bundles = get_step_bundles(&route, POLYLINE_PRECISION).unwrap();
+        let mut legs = load_extended_legs("./fixtures/valhalla-legs.json");
+
+        // A missing step or leg would otherwise go unchecked.
+        legs[1].steps.as_mut().unwrap().pop();
+        assert_eq!(
+            snap_intersections(&mut step_bundles, &legs, POLYLINE_PRECISION),
+            Err(Error::StepCountMismatch {
+                leg_index: 1,
+                expected: 4,
+                actual: 3,
+            })
+        );
+
+        legs.pop();
+        assert_eq!(
+            snap_intersections(&mut step_bundles, &legs, POLYLINE_PRECISION),
+            Err(Error::LegCountMismatch {
+                expected: 2,
+                actual: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_get_step_bundles_annotation_length_mismatch() {
+        let mut route = load_route("./fixtures/valhalla-short.json", 0);
+        let annotation = route.legs.as_mut().unwrap()[0].annotation.as_mut().unwrap();
+        annotation.duration.as_mut().unwrap().push(1.0);
+
+        assert_eq!(
+            get_step_bundles(&route, POLYLINE_PRECISION),
+            Err(Error::AnnotationLengthMismatch {
+                leg_index: 0,
+                expected: 144,
+                actual: 145,
+            })
+        );
+    }
+
+    #[test]
+    fn test_get_step_bundles_annotation_out_of_bounds() {
+        // A short annotation fails the step running past its end rather than being clamped.
+        let mut route = load_route("./fixtures/valhalla-short.json", 0);
+        let annotation = route.legs.as_mut().unwrap()[0].annotation.as_mut().unwrap();
+        annotation.duration.as_mut().unwrap().pop();
+
+        assert_eq!(
+            get_step_bundles(&route, POLYLINE_PRECISION),
+            Err(Error::AnnotationOutOfBounds {
+                field: "duration",
+                start: 137,
+                end: 143,
+                len: 143,
+            }
+            .in_step(0, 4))
+        );
+    }
+
+    /// Re-encodes every step's polyline at `polyline_precision`, as OSRM would return the route.
+    fn encode_steps(route: &mut Route, polyline_precision: u32) {
+        for step in route
+            .legs
+            .iter_mut()
+            .flatten()
+            .flat_map(|leg| leg.steps.iter_mut().flatten())
+        {
+            let coordinates = step
+                .geometry_coordinates(POLYLINE_PRECISION)
+                .unwrap()
+                .unwrap();
+            step.geometry = Some(Value::String(
+                encode_coordinates(coordinates, polyline_precision).unwrap(),
+            ));
+        }
+    }
+
+    #[test]
+    fn test_detect_polyline_precision() {
+        let mut route = load_route("./fixtures/valhalla-legs.json", 0);
+        assert_eq!(detect_polyline_precision(&route, 5), Ok(6));
+        assert_eq!(detect_polyline_precision(&route, 6), Ok(6));
+
+        encode_steps(&mut route, 5);
+        assert_eq!(detect_polyline_precision(&route, 5), Ok(5));
+        assert_eq!(detect_polyline_precision(&route, 6), Ok(5));
+    }
+
+    #[test]
+    fn test_detect_polyline_precision_falls_back() {
+        // Without polylines, there is nothing to detect from.
+        assert_eq!(detect_polyline_precision(&Route::default(), 5), Ok(5));
+
+        let mut route = load_route("./fixtures/valhalla-short.json", 0);
+        for step in route.legs.as_mut().unwrap()[0].steps.as_mut().unwrap() {
+            step.geometry = Some(serde_json::json!({
+                "type": "LineString",
+                "coordinates": step.geometry_coordinates(POLYLINE_PRECISION).unwrap().unwrap()
+                    .iter().map(|coord| vec![coord.x, coord.y]).collect::<Vec<_>>(),
+            }));
+        }
+        assert_eq!(detect_polyline_precision(&route, 5), Ok(5));
+    }
+
+    #[test]
+    fn test_detect_polyline_precision_unknown() {
+        // A step far from its maneuver location fits neither precision.
+        let mut route = load_route("./fixtures/valhalla-short.json", 0);
+        let step = &mut route.legs.as_mut().unwrap()[0].steps.as_mut().unwrap()[1];
+        step.maneuver.as_mut().unwrap().location = Some(vec![-104.99, 39.74]);
+
+        assert_eq!(
+            detect_polyline_precision(&route, 6),
+            Err(Error::UnknownPolylinePrecision)
+        );
+    }
 }
@@ -0,0 +1,486 @@
+//! An HTTP proxy in front of an OSRM compatible server, adding instructions to its routes.
+//!
+//! Requests are forwarded upstream with their method, headers and body. Responses to
+//! `/route/v1/...` are enhanced with [`enhance_route_response_json`], everything else is streamed
+//! back as is.
+
+use std::{
+    io::{self, Cursor, Read},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use serde_json::Value;
+use tiny_http::{Header, Request, Response, Server, StatusCode};
+use ureq::{Agent, Body, BodyReader, http};
+
+use crate::response::{EnhanceJsonOptions, EnhanceOptions, enhance_route_response_json};
+
+/// Upstream route responses are read up to this many bytes to be enhanced.
+const MAXIMUM_RESPONSE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Request bodies are read up to this many bytes.
+const MAXIMUM_REQUEST_BYTES: u64 = 1024 * 1024;
+
+/// Headers which are not forwarded in either direction. Most only apply to a single connection
+/// (RFC 9110 section 7.6.1). `Host` and `Content-Length` are set again for the new message, and
+/// `Accept-Encoding` is left to the agent, which decodes what it asks for so routes can be read.
+const UNFORWARDED_HEADERS: [&str; 12] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "host",
+    "content-length",
+    "accept-encoding",
+];
+
+#[derive(Debug, Clone)]
+pub struct ProxyConfig {
+    /// Base URL of the upstream server, e.g. "http://localhost:8002".
+    pub upstream: String,
+    /// Options for enhancing routes. The polyline precision applies unless the request
+    /// asks for `geometries=polyline` or `geometries=polyline6`.
+    pub options: EnhanceJsonOptions,
+    /// Number of requests served at once. Further requests wait for a free worker.
+    pub workers: usize,
+    /// How long an upstream request may take, including reading its response. Requests taking
+    /// longer fail with a 504.
+    pub timeout: Duration,
+}
+
+/// A response to send back to the client.
+pub struct ProxyResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: ProxyBody,
+}
+
+/// The body of a response to send back to the client.
+pub enum ProxyBody {
+    /// A body read in full, e.g. an enhanced route response.
+    Bytes(Vec<u8>),
+    /// An upstream body streamed through as is, with its length when known.
+    Stream(BodyReader<'static>, Option<usize>),
+}
+
+impl ProxyResponse {
+    fn error(status: u16, message: String) -> Self {
+        ProxyResponse {
+            status,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: ProxyBody::Bytes(
+                serde_json::json!({ "code": "ProxyError", "message": message })
+                    .to_string()
+                    .into_bytes(),
+            ),
+        }
+    }
+
+    /// A 504 when the upstream request timed out, or a 502 for any other failure.
+    fn upstream_error(error: ureq::Error) -> Self {
+        let status = match error {
+            ureq::Error::Timeout(_) => 504,
+            _ => 502,
+        };
+        ProxyResponse::error(status, format!("Upstream failed: {}", error))
+    }
+}
+
+/// Serves requests on `config.workers` threads until the server is closed.
+pub fn serve(server: Server, config: ProxyConfig) {
+    let server = Arc::new(server);
+    let agent = upstream_agent(config.timeout);
+    let config = Arc::new(config);
+
+    let workers = (0..config.workers.max(1))
+        .map(|_| {
+            let server = Arc::clone(&server);
+            let agent = agent.clone();
+            let config = Arc::clone(&config);
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    if let Err(error) = respond(request, &agent, &config) {
+                        eprintln!("Failed to respond: {}", error);
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for worker in workers {
+        let _ = worker.join();
+    }
+}
+
+fn respond(mut request: Request, agent: &Agent, config: &ProxyConfig) -> io::Result<()> {
+    let response = handle(agent, config, &mut request);
+
+    let status = StatusCode(response.status);
+    let headers = response
+        .headers
+        .iter()
+        .filter_map(|(name, value)| Header::from_bytes(name.as_bytes(), value.as_bytes()).ok())
+        .collect::<Vec<_>>();
+    match response.body {
+        ProxyBody::Bytes(body) => {
+            let length = body.len();
+            request.respond(Response::new(
+                status,
+                headers,
+                Cursor::new(body),
+                Some(length),
+                None,
+            ))
+        }
+        ProxyBody::Stream(body, length) => {
+            request.respond(Response::new(status, headers, body, length, None))
+        }
+    }
+}
+
+/// Forwards `request` upstream and enhances its routes.
+pub fn handle(agent: &Agent, config: &ProxyConfig, request: &mut Request) -> ProxyResponse {
+    let path_and_query = request.url().to_string();
+    let url = format!(
+        "{}{}",
+        config.upstream.trim_end_matches('/'),
+        path_and_query
+    );
+
+    let mut body = Vec::new();
+    if let Err(error) = request
+        .as_reader()
+        .take(MAXIMUM_REQUEST_BYTES + 1)
+        .read_to_end(&mut body)
+    {
+        return ProxyResponse::error(400, format!("Failed to read request: {}", error));
+    }
+    if body.len() as u64 > MAXIMUM_REQUEST_BYTES {
+        return ProxyResponse::error(413, "Request body is too large".to_string());
+    }
+
+    let upstream_request = request
+        .headers()
+        .iter()
+        .filter(|header| is_forwarded(header.field.as_str().as_str()))
+        .fold(
+            http::Request::builder()
+                .method(request.method().as_str())
+                .uri(&url),
+            |builder, header| builder.header(header.field.as_str().as_str(), header.value.as_str()),
+        );
+    let mut upstream = match forward(agent, upstream_request, body) {
+        Ok(upstream) => upstream,
+        Err(error) => return ProxyResponse::upstream_error(error),
+    };
+
+    let status = upstream.status().as_u16();
+    let headers = upstream
+        .headers()
+        .iter()
+        .filter(|(name, _)| is_forwarded(name.as_str()))
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect::<Vec<_>>();
+    if status != 200 || !path_and_query.starts_with("/route/v1/") {
+        let length = upstream
+            .headers()
+            .get("Content-Length")
+            .and_then(|value| value.to_str().ok()?.parse().ok());
+        return ProxyResponse {
+            status,
+            headers,
+            body: ProxyBody::Stream(upstream.into_body().into_reader(), length),
+        };
+    }
+
+    let body = match upstream
+        .body_mut()
+        .with_config()
+        .limit(MAXIMUM_RESPONSE_BYTES)
+        .read_to_vec()
+    {
+        Ok(body) => body,
+        Err(error) => return ProxyResponse::upstream_error(error),
+    };
+    let passthrough = |body| ProxyResponse {
+        status,
+        headers: headers.clone(),
+        body: ProxyBody::Bytes(body),
+    };
+
+    let Ok(mut response) = serde_json::from_slice::<Value>(&body) else {
+        return passthrough(body);
+    };
+
+    let options = EnhanceJsonOptions {
+        enhance: EnhanceOptions {
+            polyline_precision: requested_polyline_precision(&path_and_query)
+                .unwrap_or(config.options.enhance.polyline_precision),
+            ..config.options.enhance.clone()
+        },
+        ..config.options.clone()
+    };
+    match enhance_route_response_json(&mut response, &options) {
+        Ok(failures) => {
+            for failure in failures {
+                eprintln!(
+                    "Route {} of {} was not enhanced: {}",
+                    failure.route_index, path_and_query, failure.error
+                );
+            }
+        }
+        Err(error) => {
+            eprintln!("{} was not enhanced: {}", path_and_query, error);
+            return passthrough(body);
+        }
+    }
+
+    passthrough(response.to_string().into_bytes())
+}
+
+/// Sends a request upstream, with a body only when the client sent one.
+fn forward(
+    agent: &Agent,
+    request: http::request::Builder,
+    body: Vec<u8>,
+) -> Result<http::Response<Body>, ureq::Error> {
+    if body.is_empty() {
+        agent.run(request.body(())?)
+    } else {
+        agent.run(request.body(body)?)
+    }
+}
+
+fn is_forwarded(name: &str) -> bool {
+    !UNFORWARDED_HEADERS
+        .iter()
+        .any(|unforwarded| name.eq_ignore_ascii_case(unforwarded))
+}
+
+/// An agent which passes upstream error statuses through rather than failing on them, and
+/// forwards any method the client uses. Requests fail with a timeout after `timeout`.
+pub fn upstream_agent(timeout: Duration) -> Agent {
+    Agent::config_builder()
+        .http_status_as_error(false)
+        .allow_non_standard_methods(true)
+        .timeout_global(Some(timeout))
+        .build()
+        .into()
+}
+
+/// The polyline precision named by the request's `geometries` parameter.
+fn requested_polyline_precision(path_and_query: &str) -> Option<u32> {
+    let (_, query) = path_and_query.split_once('?')?;
+    query
+        .split('&')
+        .find_map(|parameter| parameter.strip_prefix("geometries="))
+        .and_then(|geometries| match geometries {
+            "polyline" => Some(5),
+            "polyline6" => Some(6),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distance::UnitSystem;
+    use std::fs;
+
+    /// A body which is not UTF-8, like a vector tile.
+    const BINARY_BODY: &[u8] = &[0x1a, 0xff, 0x00, 0xfe, 0x80];
+
+    /// How long the proxy waits for the stub upstream.
+    const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Serves `fixture` for every request, as an upstream server would. `/echo` answers with the
+    /// request's method and `X-Token` header, and its body, and `/stall` answers only after the
+    /// proxy has timed out.
+    fn stub_upstream(fixture: &'static str) -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let address = server.server_addr().to_ip().unwrap();
+
+        thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let response = if request.url().starts_with("/missing") {
+                    Response::from_string(r#"{"code":"InvalidUrl"}"#).with_status_code(400)
+                } else if request.url().starts_with("/stall") {
+                    thread::sleep(UPSTREAM_TIMEOUT * 2);
+                    Response::from_data(fs::read(fixture).unwrap())
+                } else if request.url().starts_with("/binary") {
+                    Response::from_data(BINARY_BODY.to_vec())
+                } else if request.url().starts_with("/echo") {
+                    let token = request
+                        .headers()
+                        .iter()
+                        .find(|header| header.field.equiv("X-Token"))
+                        .map(|header| header.value.to_string())
+                        .unwrap_or_default();
+                    let method = request.method().to_string();
+                    let mut body = Vec::new();
+                    request.as_reader().read_to_end(&mut body).unwrap();
+                    Response::from_data(body)
+                        .with_header(Header::from_bytes("X-Method", method).unwrap())
+                        .with_header(Header::from_bytes("X-Token", token).unwrap())
+                } else {
+                    Response::from_data(fs::read(fixture).unwrap())
+                };
+                let _ = request.respond(response);
+            }
+        });
+
+        format!("http://{}", address)
+    }
+
+    fn proxy(upstream: String) -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let address = server.server_addr().to_ip().unwrap();
+        let config = ProxyConfig {
+            upstream,
+            options: EnhanceJsonOptions {
+                enhance: EnhanceOptions {
+                    unit_system: UnitSystem::ImperialUs,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            workers: 2,
+            timeout: UPSTREAM_TIMEOUT,
+        };
+
+        thread::spawn(move || serve(server, config));
+
+        format!("http://{}", address)
+    }
+
+    /// A client which waits for the proxy longer than the proxy waits for the upstream.
+    fn client() -> Agent {
+        upstream_agent(UPSTREAM_TIMEOUT * 10)
+    }
+
+    #[test]
+    fn test_proxy_enhances_routes() {
+        let proxy = proxy(stub_upstream("./fixtures/valhalla-short.json"));
+
+        let body = client()
+            .get(format!(
+                "{}/route/v1/driving/-108.36,39.11;-108.54,39.09?steps=true",
+                proxy
+            ))
+            .call()
+            .unwrap()
+            .body_mut()
+            .read_to_string()
+            .unwrap();
+        let response: Value = serde_json::from_str(&body).unwrap();
+
+        let step = &response["routes"][0]["legs"][0]["steps"][0];
+        assert_eq!(step["speedLimitUnit"], "mph");
+        assert!(step["bannerInstructions"].is_array());
+        assert_eq!(
+            step["voiceInstructions"][0]["announcement"],
+            "Drive east on G 7, 10 Road, G.7."
+        );
+    }
+
+    #[test]
+    fn test_proxy_passes_through() {
+        let proxy = proxy(stub_upstream("./fixtures/valhalla-short.json"));
+        let agent = client();
+
+        let mut response = agent.get(format!("{}/missing", proxy)).call().unwrap();
+        assert_eq!(response.status().as_u16(), 400);
+        assert_eq!(
+            response.body_mut().read_to_string().unwrap(),
+            r#"{"code":"InvalidUrl"}"#
+        );
+
+        // Only route requests are enhanced.
+        let body = agent
+            .get(format!("{}/nearest/v1/driving/-108.36,39.11", proxy))
+            .call()
+            .unwrap()
+            .body_mut()
+            .read_to_string()
+            .unwrap();
+        assert_eq!(
+            body,
+            fs::read_to_string("./fixtures/valhalla-short.json").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_proxy_forwards_requests() {
+        let proxy = proxy(stub_upstream("./fixtures/valhalla-short.json"));
+
+        let mut response = client()
+            .post(format!("{}/echo", proxy))
+            .header("X-Token", "secret")
+            .send("coordinates")
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(response.headers()["X-Method"], "POST");
+        assert_eq!(response.headers()["X-Token"], "secret");
+        assert_eq!(response.body_mut().read_to_string().unwrap(), "coordinates");
+    }
+
+    #[test]
+    fn test_proxy_streams_binary_bodies() {
+        let proxy = proxy(stub_upstream("./fixtures/valhalla-short.json"));
+
+        let body = client()
+            .get(format!(
+                "{}/binary/tile/v1/car/tile(1310,3166,13).mvt",
+                proxy
+            ))
+            .call()
+            .unwrap()
+            .body_mut()
+            .read_to_vec()
+            .unwrap();
+        assert_eq!(body, BINARY_BODY);
+    }
+
+    #[test]
+    fn test_proxy_times_out() {
+        let proxy = proxy(stub_upstream("./fixtures/valhalla-short.json"));
+
+        let mut response = client()
+            .get(format!("{}/stall/route/v1/driving/-108.36,39.11", proxy))
+            .call()
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 504);
+        let body: Value =
+            serde_json::from_str(&response.body_mut().read_to_string().unwrap()).unwrap();
+        assert_eq!(body["code"], "ProxyError");
+    }
+
+    #[test]
+    fn test_requested_polyline_precision() {
+        assert_eq!(
+            requested_polyline_precision("/route/v1/driving/0,0;1,1?geometries=polyline"),
+            Some(5)
+        );
+        assert_eq!(
+            requested_polyline_precision(
+                "/route/v1/driving/0,0;1,1?steps=true&geometries=polyline6"
+            ),
+            Some(6)
+        );
+        assert_eq!(
+            requested_polyline_precision("/route/v1/driving/0,0;1,1?geometries=geojson"),
+            None
+        );
+        assert_eq!(
+            requested_polyline_precision("/route/v1/driving/0,0;1,1"),
+            None
+        );
+    }
+}
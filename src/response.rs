@@ -0,0 +1,438 @@
+use osrm_openapi_models::models::{RouteResponse, Waypoint};
+use serde_json::Value;
+
+use crate::{
+    Error,
+    distance::UnitSystem,
+    instructions::{InstructionsFactory, StepExplanation, ssml::SsmlOptions},
+    locale::Locale,
+};
+
+/// Options applied to every route of a response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnhanceOptions {
+    /// Precision of the encoded polylines, 5 for OSRM and 6 for Valhalla.
+    pub polyline_precision: u32,
+    /// Detects each route's polyline precision, keeping `polyline_precision` when it fits.
+    pub automatic_polyline_precision: bool,
+    pub unit_system: UnitSystem,
+    pub locale: Locale,
+    pub ssml_options: SsmlOptions,
+    /// Announces where the speed limit changes, from the `maxspeed` annotations.
+    pub speed_limit_announcements: bool,
+}
+
+impl Default for EnhanceOptions {
+    fn default() -> Self {
+        EnhanceOptions {
+            polyline_precision: 6,
+            automatic_polyline_precision: false,
+            unit_system: UnitSystem::default(),
+            locale: Locale::default(),
+            ssml_options: SsmlOptions::default(),
+            speed_limit_announcements: false,
+        }
+    }
+}
+
+/// Options applied to every route of a response's JSON, which can also use the fields the
+/// OpenAPI model drops.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EnhanceJsonOptions {
+    pub enhance: EnhanceOptions,
+    /// Picks each step's unit system from its speed limit unit and country. Steps without either
+    /// use `enhance.unit_system`.
+    pub automatic_unit_system: bool,
+    /// Fails routes whose intersections' `geometry_index` disagrees with their step geometry.
+    pub validate_intersections: bool,
+}
+
+/// A route which could not be enhanced. It is left as the server returned it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteFailure {
+    /// Index of the route within the response's routes.
+    pub route_index: usize,
+    pub error: Error,
+}
+
+/// Adds banner and voice instructions to every route of `response` in place, the first route and
+/// its alternatives alike, naming stops after the response's waypoints.
+///
+/// A route that fails is left unchanged and reported, while the others are still enhanced. The
+/// automatic unit system and intersection validation need fields the OpenAPI model drops, so they
+/// are only offered by [`enhance_route_response_json`].
+pub fn enhance_route_response(
+    response: &mut RouteResponse,
+    options: &EnhanceOptions,
+) -> Vec<RouteFailure> {
+    let factory = instructions_factory(options, response.waypoints.clone().unwrap_or_default());
+
+    response
+        .routes
+        .iter_mut()
+        .flatten()
+        .enumerate()
+        .filter_map(|(route_index, route)| {
+            factory
+                .apply_in_place(route)
+                .err()
+                .map(|error| RouteFailure { route_index, error })
+        })
+        .collect()
+}
+
+/// Adds banner and voice instructions to every route of a route response's JSON in place.
+///
+/// Unlike [`enhance_route_response`], nothing is lost to the OpenAPI model: the response is the
+/// original with `bannerInstructions` and `voiceInstructions` added to each step, and banners
+/// keep their `sub` banner and lanes. A route that fails is left unchanged and reported, and an
+/// error is only returned when the response's waypoints can't be read.
+pub fn enhance_route_response_json(
+    response: &mut Value,
+    options: &EnhanceJsonOptions,
+) -> Result<Vec<RouteFailure>, Error> {
+    let factory = json_instructions_factory(options, json_waypoints(response)?);
+
+    let Some(routes) = response.get_mut("routes").and_then(Value::as_array_mut) else {
+        return Ok(Vec::new());
+    };
+
+    let failures = routes
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(route_index, route)| {
+            factory
+                .apply_to_json(route)
+                .err()
+                .map(|error| RouteFailure { route_index, error })
+        })
+        .collect();
+
+    Ok(failures)
+}
+
+/// The explained steps of a route, indexed by `[leg][step]`, or why it can't be enhanced.
+pub type RouteExplanation = Result<Vec<Vec<StepExplanation>>, Error>;
+
+/// Explains the instructions [`enhance_route_response_json`] would add to every route of a route
+/// response's JSON, indexed by `[route]`.
+pub fn explain_route_response_json(
+    response: &Value,
+    options: &EnhanceJsonOptions,
+) -> Result<Vec<RouteExplanation>, Error> {
+    let factory = json_instructions_factory(options, json_waypoints(response)?);
+
+    let Some(routes) = response.get("routes").and_then(Value::as_array) else {
+        return Ok(Vec::new());
+    };
+
+    let explanations = routes
+        .iter()
+        .map(|route| factory.explain_json(route))
+        .collect();
+
+    Ok(explanations)
+}
+
+fn json_waypoints(response: &Value) -> Result<Vec<Waypoint>, Error> {
+    match response.get("waypoints") {
+        Some(waypoints) => serde_json::from_value(waypoints.clone())
+            .map_err(|error| Error::InvalidJson(error.to_string())),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn instructions_factory(options: &EnhanceOptions, waypoints: Vec<Waypoint>) -> InstructionsFactory {
+    let factory = InstructionsFactory::new(options.polyline_precision, options.unit_system)
+        .with_waypoints(waypoints)
+        .with_ssml_options(options.ssml_options.clone())
+        .with_locale(options.locale);
+
+    let factory = if options.automatic_polyline_precision {
+        factory.with_automatic_polyline_precision()
+    } else {
+        factory
+    };
+
+    if options.speed_limit_announcements {
+        factory.with_speed_limit_announcements()
+    } else {
+        factory
+    }
+}
+
+fn json_instructions_factory(
+    options: &EnhanceJsonOptions,
+    waypoints: Vec<Waypoint>,
+) -> InstructionsFactory {
+    let factory = instructions_factory(&options.enhance, waypoints);
+
+    let factory = if options.automatic_unit_system {
+        factory.with_automatic_unit_system()
+    } else {
+        factory
+    };
+
+    if options.validate_intersections {
+        factory.with_intersection_validation()
+    } else {
+        factory
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fixtures::load_route_response, load_route};
+
+    #[test]
+    fn test_enhance_route_response() {
+        let mut response = load_route_response("./fixtures/valhalla-legs.json");
+        let alternative = response.routes.as_ref().unwrap()[0].clone();
+        response.routes.as_mut().unwrap().push(alternative);
+
+        let failures = enhance_route_response(&mut response, &EnhanceOptions::default());
+        assert!(failures.is_empty());
+
+        let expected = InstructionsFactory::new(6, UnitSystem::Metric)
+            .with_waypoints(response.waypoints.clone().unwrap())
+            .apply(load_route("./fixtures/valhalla-legs.json", 0))
+            .unwrap();
+        assert_eq!(response.routes.unwrap(), vec![expected.clone(), expected]);
+    }
+
+    #[test]
+    fn test_enhance_route_response_matches_json_without_extended_options() {
+        let voice_instructions = |response: &Value| {
+            response["routes"][0]["legs"][1]["steps"][1]["voiceInstructions"].clone()
+        };
+        let original: Value = serde_json::from_str(
+            &std::fs::read_to_string("./fixtures/valhalla-legs.json").unwrap(),
+        )
+        .unwrap();
+
+        let mut typed = load_route_response("./fixtures/valhalla-legs.json");
+        enhance_route_response(&mut typed, &EnhanceOptions::default());
+        let typed = serde_json::to_value(&typed).unwrap();
+
+        let mut json = original.clone();
+        enhance_route_response_json(&mut json, &EnhanceJsonOptions::default()).unwrap();
+        assert_eq!(voice_instructions(&json), voice_instructions(&typed));
+
+        // Only the JSON entry point can read the admins, announcing the US leg in miles.
+        let mut automatic = original;
+        let options = EnhanceJsonOptions {
+            automatic_unit_system: true,
+            ..Default::default()
+        };
+        enhance_route_response_json(&mut automatic, &options).unwrap();
+        assert_ne!(voice_instructions(&automatic), voice_instructions(&typed));
+        assert!(
+            voice_instructions(&automatic)[0]["announcement"]
+                .as_str()
+                .unwrap()
+                .contains("mile")
+        );
+    }
+
+    #[test]
+    fn test_enhance_route_response_reports_failures() {
+        let mut response = load_route_response("./fixtures/valhalla-short.json");
+        let mut alternative = response.routes.as_ref().unwrap()[0].clone();
+        alternative.legs.as_mut().unwrap()[0]
+            .steps
+            .as_mut()
+            .unwrap()[2]
+            .geometry = None;
+        response.routes.as_mut().unwrap().push(alternative.clone());
+
+        let failures = enhance_route_response(&mut response, &EnhanceOptions::default());
+        assert_eq!(
+            failures,
+            vec![RouteFailure {
+                route_index: 1,
+                error: Error::MissingGeometry.in_step(0, 2),
+            }]
+        );
+
+        // The failed alternative is untouched, while the first route is still enhanced.
+        let routes = response.routes.unwrap();
+        assert_eq!(routes[1], alternative);
+        assert!(
+            routes[0].legs.as_ref().unwrap()[0].steps.as_ref().unwrap()[0]
+                .banner_instructions
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_enhance_route_response_json() {
+        let original: Value = serde_json::from_str(
+            &std::fs::read_to_string("./fixtures/valhalla-legs.json").unwrap(),
+        )
+        .unwrap();
+        let mut response = original.clone();
+
+        let options = EnhanceJsonOptions {
+            automatic_unit_system: true,
+            ..Default::default()
+        };
+        let failures = enhance_route_response_json(&mut response, &options).unwrap();
+        assert!(failures.is_empty());
+
+        // Fields the OpenAPI model doesn't describe are kept.
+        let leg = &response["routes"][0]["legs"][1];
+        assert_eq!(leg["admins"], original["routes"][0]["legs"][1]["admins"]);
+        assert_eq!(leg["steps"][1]["speedLimitUnit"], "mph");
+        assert_eq!(leg["steps"][1]["speedLimitSign"], "mutcd");
+
+        // The instructions match the typed entry point, announced in miles for the US.
+        let mut typed = load_route_response("./fixtures/valhalla-legs.json");
+        let typed_options = EnhanceOptions {
+            unit_system: UnitSystem::ImperialUs,
+            ..Default::default()
+        };
+        enhance_route_response(&mut typed, &typed_options);
+        let typed_routes = typed.routes.unwrap();
+        let typed_step = &typed_routes[0].legs.as_ref().unwrap()[1]
+            .steps
+            .as_ref()
+            .unwrap()[1];
+        assert_eq!(
+            leg["steps"][1]["voiceInstructions"],
+            serde_json::to_value(&typed_step.voice_instructions).unwrap()
+        );
+
+        // Only the instructions changed.
+        assert_eq!(
+            without_instructions(response),
+            without_instructions(original)
+        );
+    }
+
+    fn without_instructions(mut response: Value) -> Value {
+        for leg in response["routes"][0]["legs"].as_array_mut().unwrap() {
+            for step in leg["steps"].as_array_mut().unwrap() {
+                let step = step.as_object_mut().unwrap();
+                step.remove("bannerInstructions");
+                step.remove("voiceInstructions");
+            }
+        }
+        response
+    }
+
+    #[test]
+    fn test_enhance_route_response_json_reports_failures() {
+        let mut response: Value = serde_json::from_str(
+            &std::fs::read_to_string("./fixtures/valhalla-short.json").unwrap(),
+        )
+        .unwrap();
+        response["routes"][0]["legs"][0]["steps"][1]["geometry"] = Value::Null;
+        let original = response.clone();
+
+        let failures = enhance_route_response_json(&mut response, &EnhanceJsonOptions::default());
+        assert_eq!(
+            failures,
+            Ok(vec![RouteFailure {
+                route_index: 0,
+                error: Error::MissingGeometry.in_step(0, 1),
+            }])
+        );
+        assert_eq!(response, original);
+    }
+
+    #[test]
+    fn test_enhance_route_response_json_reports_unreadable_legs() {
+        let mut response: Value = serde_json::from_str(
+            &std::fs::read_to_string("./fixtures/valhalla-short.json").unwrap(),
+        )
+        .unwrap();
+        response["routes"][0]["legs"][0]["admins"] = serde_json::json!("US");
+        let original = response.clone();
+
+        // The admins are only read for the automatic unit system.
+        let mut enhanced = response.clone();
+        let failures = enhance_route_response_json(&mut enhanced, &EnhanceJsonOptions::default());
+        assert_eq!(failures, Ok(Vec::new()));
+
+        let options = EnhanceJsonOptions {
+            automatic_unit_system: true,
+            ..Default::default()
+        };
+        let failures = enhance_route_response_json(&mut response, &options).unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].route_index, 0);
+        assert!(matches!(failures[0].error, Error::InvalidJson(_)));
+        assert_eq!(response, original);
+
+        let explanations = explain_route_response_json(&response, &options).unwrap();
+        assert!(matches!(explanations[0], Err(Error::InvalidJson(_))));
+    }
+
+    #[test]
+    fn test_enhance_route_response_json_validates_intersections() {
+        let mut response: Value = serde_json::from_str(
+            &std::fs::read_to_string("./fixtures/valhalla-short.json").unwrap(),
+        )
+        .unwrap();
+        let options = EnhanceJsonOptions {
+            validate_intersections: true,
+            ..Default::default()
+        };
+
+        let explanations = explain_route_response_json(&response, &options).unwrap();
+        let step = &explanations[0].as_ref().unwrap()[0][2];
+        assert_eq!(step.intersections.len(), 9);
+        assert_eq!(step.intersections[1].geometry_index, 18);
+
+        response["routes"][0]["legs"][0]["steps"][2]["intersections"][1]["geometry_index"] =
+            serde_json::json!(40);
+        let original = response.clone();
+        let failures = enhance_route_response_json(&mut response, &options);
+        assert_eq!(
+            failures,
+            Ok(vec![RouteFailure {
+                route_index: 0,
+                error: Error::IntersectionIndexMismatch {
+                    intersection_index: 1,
+                    geometry_index: 40,
+                    first: 13,
+                    last: 67,
+                }
+                .in_step(0, 2),
+            }])
+        );
+        assert_eq!(response, original);
+    }
+
+    #[test]
+    fn test_explain_route_response_json() {
+        let response: Value = serde_json::from_str(
+            &std::fs::read_to_string("./fixtures/valhalla-short.json").unwrap(),
+        )
+        .unwrap();
+
+        let explanations =
+            explain_route_response_json(&response, &EnhanceJsonOptions::default()).unwrap();
+        let steps = explanations[0].as_ref().unwrap();
+
+        // Each step explains every type of announcement, and the last step has none.
+        let step = &steps[0][2];
+        assert_eq!((step.leg_index, step.step_index), (0, 2));
+        assert_eq!(step.announcements.len(), 6);
+        assert!(steps[0].last().unwrap().announcements.is_empty());
+
+        // The announcements made are the ones the enhanced response gets.
+        let mut enhanced = response.clone();
+        enhance_route_response_json(&mut enhanced, &EnhanceJsonOptions::default()).unwrap();
+        let made = step
+            .announcements
+            .iter()
+            .filter_map(|(_, instruction)| instruction.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            serde_json::to_value(made).unwrap(),
+            enhanced["routes"][0]["legs"][0]["steps"][2]["voiceInstructions"]
+        );
+    }
+}
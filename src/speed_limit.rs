@@ -0,0 +1,303 @@
+//! Speed limits along a route, read from the `maxspeed` annotation.
+
+use osrm_openapi_models::models::{MaxSpeed, Route, max_speed};
+
+use crate::{
+    Error,
+    distance::UnitSystem,
+    geo::step_geometry::StepGeometry,
+    osrm::{RouteStepBundle, utilities::get_step_bundles},
+};
+
+/// The unit a speed limit is posted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeedUnit {
+    KilometersPerHour,
+    MilesPerHour,
+}
+
+impl SpeedUnit {
+    /// The unit speed limits are signed in where `unit_system` is used.
+    pub fn for_unit_system(unit_system: UnitSystem) -> Self {
+        match unit_system {
+            UnitSystem::Metric => SpeedUnit::KilometersPerHour,
+            UnitSystem::ImperialUs | UnitSystem::ImperialUk => SpeedUnit::MilesPerHour,
+        }
+    }
+
+    pub fn meters_per_second(&self, speed: f64) -> f64 {
+        match self {
+            SpeedUnit::KilometersPerHour => speed / 3.6,
+            SpeedUnit::MilesPerHour => speed * 0.44704,
+        }
+    }
+
+    pub fn from_meters_per_second(&self, meters_per_second: f64) -> f64 {
+        meters_per_second / self.meters_per_second(1.0)
+    }
+}
+
+impl From<max_speed::Unit> for SpeedUnit {
+    fn from(unit: max_speed::Unit) -> Self {
+        match unit {
+            max_speed::Unit::KmSlashH => SpeedUnit::KilometersPerHour,
+            max_speed::Unit::Mph => SpeedUnit::MilesPerHour,
+        }
+    }
+}
+
+/// The speed limit of a segment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpeedLimit {
+    /// A posted limit.
+    Posted { speed: u32, unit: SpeedUnit },
+    /// There is no limit, e.g. on some Autobahn sections.
+    Unlimited,
+    /// The limit is not known.
+    Unknown,
+}
+
+impl SpeedLimit {
+    /// Reads a `maxspeed` annotation entry, i.e. `{speed, unit}`, `{none}` or `{unknown}`.
+    ///
+    /// OSRM always includes the unit with a speed, so a missing unit is taken as km/h.
+    pub fn from_max_speed(max_speed: &MaxSpeed) -> Self {
+        match max_speed {
+            MaxSpeed {
+                speed: Some(speed), ..
+            } if *speed >= 0 => SpeedLimit::Posted {
+                speed: *speed as u32,
+                unit: max_speed.unit.unwrap_or_default().into(),
+            },
+            MaxSpeed {
+                none: Some(true), ..
+            } => SpeedLimit::Unlimited,
+            _ => SpeedLimit::Unknown,
+        }
+    }
+
+    /// The posted limit in meters per second.
+    pub fn meters_per_second(&self) -> Option<f64> {
+        match self {
+            SpeedLimit::Posted { speed, unit } => Some(unit.meters_per_second(*speed as f64)),
+            SpeedLimit::Unlimited | SpeedLimit::Unknown => None,
+        }
+    }
+
+    /// The posted limit in `unit`, rounded to a whole number as signs show it.
+    ///
+    /// Valhalla reports every limit in km/h, so a 55 mph sign comes back as 89 km/h.
+    pub fn speed_in(&self, unit: SpeedUnit) -> Option<u32> {
+        match self {
+            SpeedLimit::Posted {
+                speed,
+                unit: posted,
+            } if *posted == unit => Some(*speed),
+            _ => self
+                .meters_per_second()
+                .map(|speed| unit.from_meters_per_second(speed).round() as u32),
+        }
+    }
+}
+
+/// A stretch of a step with a single speed limit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeedLimitSpan {
+    /// Meters from the start of the step to where the span starts.
+    pub start: f64,
+    /// Meters from the start of the step to where the span ends.
+    pub end: f64,
+    pub limit: SpeedLimit,
+}
+
+/// The speed limits along a step, one span per segment of its geometry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeedLimitTimeline {
+    segments: Vec<SpeedLimitSpan>,
+}
+
+impl SpeedLimitTimeline {
+    /// Pairs each segment of `geometry` with its `maxspeed` entry. Segments without an entry are
+    /// left out.
+    pub fn new(geometry: &StepGeometry, max_speeds: &[MaxSpeed]) -> Self {
+        let segments = geometry
+            .distances()
+            .windows(2)
+            .zip(max_speeds)
+            .map(|(distances, max_speed)| SpeedLimitSpan {
+                start: distances[0],
+                end: distances[1],
+                limit: SpeedLimit::from_max_speed(max_speed),
+            })
+            .collect();
+
+        SpeedLimitTimeline { segments }
+    }
+
+    /// The timeline of a step, or `None` when its annotation has no `maxspeed`.
+    pub fn for_step(
+        bundle: &RouteStepBundle,
+        polyline_precision: u32,
+    ) -> Result<Option<Self>, Error> {
+        let Some(max_speeds) = bundle
+            .annotation
+            .as_ref()
+            .and_then(|annotation| annotation.maxspeed.as_ref())
+        else {
+            return Ok(None);
+        };
+        let geometry = StepGeometry::from_step(&bundle.step, polyline_precision)?
+            .ok_or(Error::MissingGeometry)?;
+
+        Ok(Some(SpeedLimitTimeline::new(&geometry, max_speeds)))
+    }
+
+    /// The speed limit of each segment, in order along the step.
+    pub fn segments(&self) -> &[SpeedLimitSpan] {
+        &self.segments
+    }
+
+    /// The stretches of the step with a single speed limit, merging neighbouring segments which
+    /// share one. Each span after the first starts where the limit changes.
+    pub fn spans(&self) -> Vec<SpeedLimitSpan> {
+        self.segments
+            .iter()
+            .fold(Vec::<SpeedLimitSpan>::new(), |mut spans, segment| {
+                match spans.last_mut() {
+                    Some(span) if span.limit == segment.limit => span.end = segment.end,
+                    _ => spans.push(*segment),
+                }
+                spans
+            })
+    }
+
+    /// The speed limit `distance` meters from the start of the step. Distances past the end of
+    /// the step get the limit of its last segment.
+    pub fn at_distance(&self, distance: f64) -> Option<SpeedLimit> {
+        let index = self
+            .segments
+            .partition_point(|segment| segment.end <= distance)
+            .min(self.segments.len().checked_sub(1)?);
+        Some(self.segments[index].limit)
+    }
+}
+
+/// The speed limit timelines of every step of `route`, indexed by `[leg][step]`. Steps without a
+/// `maxspeed` annotation have none.
+pub fn route_speed_limits(
+    route: &Route,
+    polyline_precision: u32,
+) -> Result<Vec<Vec<Option<SpeedLimitTimeline>>>, Error> {
+    get_step_bundles(route, polyline_precision)?
+        .iter()
+        .map(|leg_bundles| {
+            leg_bundles
+                .iter()
+                .map(|bundle| {
+                    SpeedLimitTimeline::for_step(bundle, polyline_precision)
+                        .map_err(|error| error.in_step(bundle.leg_index, bundle.step_index))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::POLYLINE_PRECISION;
+    use crate::testing::load_route;
+    use geo::Coord;
+    use insta::assert_debug_snapshot;
+
+    fn posted(speed: u32) -> SpeedLimit {
+        SpeedLimit::Posted {
+            speed,
+            unit: SpeedUnit::KilometersPerHour,
+        }
+    }
+
+    #[test]
+    fn test_from_max_speed() {
+        let max_speed = |json: serde_json::Value| serde_json::from_value(json).unwrap();
+
+        assert_eq!(
+            SpeedLimit::from_max_speed(&max_speed(serde_json::json!({"speed": 55, "unit": "mph"}))),
+            SpeedLimit::Posted {
+                speed: 55,
+                unit: SpeedUnit::MilesPerHour
+            }
+        );
+        assert_eq!(
+            SpeedLimit::from_max_speed(&max_speed(serde_json::json!({"none": true}))),
+            SpeedLimit::Unlimited
+        );
+        assert_eq!(
+            SpeedLimit::from_max_speed(&max_speed(serde_json::json!({"unknown": true}))),
+            SpeedLimit::Unknown
+        );
+        assert_eq!(posted(90).meters_per_second(), Some(25.0));
+        assert_eq!(posted(89).speed_in(SpeedUnit::MilesPerHour), Some(55));
+        assert_eq!(posted(89).speed_in(SpeedUnit::KilometersPerHour), Some(89));
+        assert_eq!(
+            SpeedLimit::Unlimited.speed_in(SpeedUnit::MilesPerHour),
+            None
+        );
+    }
+
+    #[test]
+    fn test_timeline_at_distance() {
+        let geometry = StepGeometry::new(vec![
+            Coord { x: 0.0, y: 0.0 },
+            Coord { x: 0.001, y: 0.0 },
+            Coord { x: 0.002, y: 0.0 },
+            Coord { x: 0.003, y: 0.0 },
+        ]);
+        let max_speeds = [
+            MaxSpeed {
+                speed: Some(50),
+                unit: Some(max_speed::Unit::KmSlashH),
+                ..Default::default()
+            },
+            MaxSpeed {
+                speed: Some(50),
+                unit: Some(max_speed::Unit::KmSlashH),
+                ..Default::default()
+            },
+            MaxSpeed {
+                unknown: Some(true),
+                ..Default::default()
+            },
+        ];
+        let timeline = SpeedLimitTimeline::new(&geometry, &max_speeds);
+
+        // Each segment is about 111 m long.
+        assert_eq!(timeline.segments().len(), 3);
+        assert_eq!(timeline.at_distance(0.0), Some(posted(50)));
+        assert_eq!(timeline.at_distance(200.0), Some(posted(50)));
+        assert_eq!(timeline.at_distance(250.0), Some(SpeedLimit::Unknown));
+        assert_eq!(timeline.at_distance(1000.0), Some(SpeedLimit::Unknown));
+
+        let spans = timeline.spans();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].end, spans[1].start);
+        assert_eq!(spans[1].limit, SpeedLimit::Unknown);
+
+        assert_eq!(
+            SpeedLimitTimeline::new(&geometry, &[]).at_distance(0.0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_route_speed_limits() {
+        let route = load_route("./fixtures/valhalla-short.json", 0);
+        let speed_limits = route_speed_limits(&route, POLYLINE_PRECISION).unwrap();
+
+        let spans = speed_limits[0]
+            .iter()
+            .map(|timeline| timeline.as_ref().map(SpeedLimitTimeline::spans))
+            .collect::<Vec<_>>();
+        assert_debug_snapshot!(spans);
+    }
+}
@@ -1,6 +1,9 @@
 use std::fs;
 
 use osrm_openapi_models::models::{Route, RouteResponse};
+use serde_json::Value;
+
+use crate::osrm::models::ExtendedRoute;
 
 pub fn load_route_response(file_path: &str) -> RouteResponse {
     // Read and parse the JSON file into RouteResponse
@@ -23,3 +26,20 @@ pub fn load_route(file_path: &str, route_index: usize) -> Route {
         .unwrap_or_else(|| panic!("No route at index: {},", route_index))
         .clone()
 }
+
+/// Loads the fields of a route which the OpenAPI model drops.
+pub fn load_extended_route(file_path: &str, route_index: usize) -> ExtendedRoute {
+    let json_str = fs::read_to_string(file_path)
+        .unwrap_or_else(|error| panic!("Failed to read file: {}, {}", file_path, error));
+
+    let response: Value = serde_json::from_str(&json_str)
+        .unwrap_or_else(|error| panic!("Failed to parse JSON from: {}, {}", file_path, error));
+
+    let route = response["routes"]
+        .get(route_index)
+        .unwrap_or_else(|| panic!("No route at index: {},", route_index))
+        .clone();
+
+    serde_json::from_value(route)
+        .unwrap_or_else(|error| panic!("Failed to parse route from: {}, {}", file_path, error))
+}
@@ -17,6 +17,7 @@ pub fn load_route_steps(
     let steps = get_step_bundles(&route, polyline_precision)
         .expect("Failed to get step bundles")
         .into_iter()
+        .flatten()
         .collect::<Vec<RouteStepBundle>>();
 
     // Get the current step and optionally the next step